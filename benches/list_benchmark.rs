@@ -73,5 +73,30 @@ fn psl(c: &mut Criterion) {
     });
 }
 
-criterion_group!(benches, psl);
+fn loading(c: &mut Criterion) {
+    let dat = include_str!("../tests/public_suffix_list.dat");
+    let bytes = addr::list::parse_rules(dat).unwrap().to_bytes().unwrap();
+
+    c.bench_function("addr::list::parse_rules", |b| {
+        b.iter(|| {
+            addr::list::parse_rules(dat).unwrap();
+        })
+    });
+
+    c.bench_function("addr::list::Binary::new", |b| {
+        b.iter(|| {
+            addr::list::Binary::new(&bytes).unwrap();
+        })
+    });
+
+    c.bench_function("addr::list::Binary::suffix", |b| {
+        let list = addr::list::Binary::new(&bytes).unwrap();
+
+        b.iter(|| {
+            list.suffix(b"example.com").unwrap();
+        })
+    });
+}
+
+criterion_group!(benches, psl, loading);
 criterion_main!(benches);
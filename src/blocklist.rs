@@ -0,0 +1,538 @@
+//! Block and allow lists of domain names
+//!
+//! Hosts files, adblock-style domain rules like `||example.com^` and
+//! plain lists of domains are parsed into rules, which a `Matcher` then
+//! checks hosts against. Lines that can't be used are reported with their
+//! line number, without stopping the rest of the list from loading.
+//!
+//! ```rust
+//! use addr::blocklist::{self, Action, Format, Matcher};
+//!
+//! let input = "
+//!     ! ads and trackers
+//!     ||ads.example.com^
+//!     @@||good.ads.example.com^
+//!     example.com##.banner
+//! ";
+//! let (rules, errors) = blocklist::parse(&psl::List, input, Format::Adblock);
+//! assert_eq!(errors.len(), 1);
+//! assert_eq!(errors[0].line(), 5);
+//!
+//! let matcher = Matcher::new(rules);
+//! let rule = matcher.check("cdn.ads.example.com").unwrap();
+//! assert_eq!(rule.action(), Action::Block);
+//! assert_eq!(rule.text(), "||ads.example.com^");
+//!
+//! assert!(matcher.is_blocked("pixel.ads.example.com"));
+//! assert!(!matcher.is_blocked("good.ads.example.com"));
+//! assert!(!matcher.is_blocked("example.com"));
+//! ```
+
+use crate::error::{Cause, Error, Kind};
+use crate::map::{DomainMap, Scope};
+use crate::{dns, domain};
+use alloc::vec::Vec;
+use core::fmt;
+use psl_types::List;
+
+// names in hosts files that are not meant to be blocked
+const LOCAL_NAMES: &[&str] = &[
+    "localhost",
+    "localhost.localdomain",
+    "local",
+    "broadcasthost",
+    "ip6-localhost",
+    "ip6-loopback",
+    "ip6-localnet",
+    "ip6-mcastprefix",
+    "ip6-allnodes",
+    "ip6-allrouters",
+    "ip6-allhosts",
+    "0.0.0.0",
+];
+
+/// The format of a list
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum Format {
+    /// `0.0.0.0 ads.example.com`, blocking exactly the names listed
+    ///
+    /// Comments start with `#`. Names like `localhost` are skipped.
+    Hosts,
+    /// `||ads.example.com^`, blocking the name and its subdomains
+    ///
+    /// `@@||ads.example.com^` allows them instead. Comments start with
+    /// `!`. Rules with options, paths or wildcards, and cosmetic rules,
+    /// are reported as `UnsupportedRule`.
+    Adblock,
+    /// `ads.example.com`, blocking exactly the name
+    ///
+    /// `*.ads.example.com` blocks the name and all its subdomains, like
+    /// adblock rules do. Comments start with `#`.
+    Domains,
+}
+
+/// What to do with the names a rule applies to
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Action {
+    Block,
+    Allow,
+}
+
+/// A rule from a list
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Rule<'a> {
+    line: usize,
+    text: &'a str,
+    name: dns::Name<'a>,
+    scope: Scope,
+    action: Action,
+}
+
+impl<'a> Rule<'a> {
+    /// The line the rule is on, counting from 1
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The line the rule is on, without surrounding whitespace
+    pub const fn text(&self) -> &'a str {
+        self.text
+    }
+
+    /// The name the rule is for
+    pub const fn name(&self) -> dns::Name<'a> {
+        self.name
+    }
+
+    /// Whether the rule also applies to subdomains
+    pub const fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// Whether the rule blocks or allows
+    pub const fn action(&self) -> Action {
+        self.action
+    }
+}
+
+impl fmt::Display for Rule<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(self.text)
+    }
+}
+
+/// An error on a line of a list
+///
+/// The input of the error is the line, without surrounding whitespace.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct LineError<'a> {
+    line: usize,
+    error: Error<'a>,
+}
+
+impl<'a> LineError<'a> {
+    /// The line of the error, counting from 1
+    pub const fn line(&self) -> usize {
+        self.line
+    }
+
+    /// The error itself
+    pub const fn error(&self) -> Error<'a> {
+        self.error
+    }
+}
+
+impl fmt::Display for LineError<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        fmt::Display::fmt(&self.error, f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for LineError<'_> {}
+
+/// Parses a list, returning the rules and the errors in it
+///
+/// Names are checked like `parse_domain_name` does, except in hosts
+/// files which may have any DNS name.
+pub fn parse<'a, L>(list: &L, input: &'a str, format: Format) -> (Vec<Rule<'a>>, Vec<LineError<'a>>)
+where
+    L: List + ?Sized,
+{
+    let mut rules = Vec::new();
+    let mut errors = Vec::new();
+    for (index, line) in input.lines().enumerate() {
+        let text = line.trim();
+        let mut rule = |name: &'a str, scope: Scope, action: Action| -> Result<(), Cause> {
+            // the name is a slice of `text`
+            let offset = name.as_ptr() as usize - text.as_ptr() as usize;
+            if format != Format::Hosts {
+                domain::Name::parse(list, name).map_err(|cause| cause.shift(offset))?;
+            }
+            let name = dns::Name::parse(list, name).map_err(|cause| cause.shift(offset))?;
+            rules.push(Rule {
+                line: index + 1,
+                text,
+                name,
+                scope,
+                action,
+            });
+            Ok(())
+        };
+        let result = match format {
+            Format::Hosts => parse_hosts(text, &mut rule),
+            Format::Adblock => parse_adblock(text, &mut rule),
+            Format::Domains => parse_domains(text, &mut rule),
+        };
+        if let Err(cause) = result {
+            errors.push(LineError {
+                line: index + 1,
+                error: cause.error_with(text),
+            });
+        }
+    }
+    (rules, errors)
+}
+
+fn parse_hosts<'a, F>(text: &'a str, rule: &mut F) -> Result<(), Cause>
+where
+    F: FnMut(&'a str, Scope, Action) -> Result<(), Cause>,
+{
+    let text = match text.find('#') {
+        Some(index) => &text[..index],
+        None => text,
+    };
+    let mut tokens = text.split_whitespace();
+    let address = match tokens.next() {
+        Some(address) => address,
+        None => return Ok(()),
+    };
+    if !is_address(address) {
+        let start = address.as_ptr() as usize - text.as_ptr() as usize;
+        return Err(Kind::UnsupportedRule.at(start, start + address.len()));
+    }
+    for host in tokens {
+        if !LOCAL_NAMES
+            .iter()
+            .any(|local| local.eq_ignore_ascii_case(host))
+        {
+            rule(host, Scope::Exact, Action::Block)?;
+        }
+    }
+    Ok(())
+}
+
+// whether the first field of a hosts line is an IPv4 or IPv6 address
+fn is_address(field: &str) -> bool {
+    if field.contains(':') {
+        // maybe with a zone, like `fe80::1%lo0`
+        let mut parts = field.splitn(2, '%');
+        let address = parts.next().unwrap_or_default();
+        parts.next().map_or(true, |zone| !zone.is_empty()) && is_ipv6(address)
+    } else {
+        is_ipv4(field)
+    }
+}
+
+// eight groups of up to four hex digits, where `::` stands for one or
+// more groups of zeros and the last two groups can be an IPv4 address
+// https://tools.ietf.org/html/rfc4291#section-2.2
+fn is_ipv6(address: &str) -> bool {
+    let mut parts = address.splitn(2, "::");
+    let head = parts.next().unwrap_or_default();
+    match parts.next() {
+        Some(tail) => match (groups(head, false), groups(tail, true)) {
+            (Some(head), Some(tail)) => head + tail < 8,
+            _ => false,
+        },
+        None => groups(head, true) == Some(8),
+    }
+}
+
+// the number of 16-bit groups in a part of an IPv6 address
+fn groups(part: &str, ipv4_tail: bool) -> Option<usize> {
+    if part.is_empty() {
+        return Some(0);
+    }
+    let mut count = 0;
+    let mut fields = part.split(':').peekable();
+    while let Some(group) = fields.next() {
+        let is_last = fields.peek().is_none();
+        if ipv4_tail && is_last && group.contains('.') {
+            if !is_ipv4(group) {
+                return None;
+            }
+            count += 2;
+        } else if (1..=4).contains(&group.len()) && group.bytes().all(|b| b.is_ascii_hexdigit()) {
+            count += 1;
+        } else {
+            return None;
+        }
+    }
+    Some(count)
+}
+
+fn is_ipv4(field: &str) -> bool {
+    let mut octets = 0;
+    for octet in field.split('.') {
+        if !octet.bytes().all(|b| b.is_ascii_digit()) || octet.parse::<u8>().is_err() {
+            return false;
+        }
+        octets += 1;
+    }
+    octets == 4
+}
+
+fn parse_adblock<'a, F>(text: &'a str, rule: &mut F) -> Result<(), Cause>
+where
+    F: FnMut(&'a str, Scope, Action) -> Result<(), Cause>,
+{
+    if text.is_empty() || text.starts_with('!') || text.starts_with('[') {
+        return Ok(());
+    }
+    let unsupported = Kind::UnsupportedRule.at(0, text.len());
+    let (action, pattern) = if text.starts_with("@@") {
+        (Action::Allow, &text[2..])
+    } else {
+        (Action::Block, text)
+    };
+    if !pattern.starts_with("||") {
+        return Err(unsupported);
+    }
+    let pattern = &pattern[2..];
+    let name = match pattern.find('^') {
+        // nothing may follow the separator, not even options
+        Some(index) if index + 1 == pattern.len() => &pattern[..index],
+        _ => return Err(unsupported),
+    };
+    if name.contains(|c| c == '*' || c == '/' || c == '|' || c == '$') {
+        return Err(unsupported);
+    }
+    rule(name, Scope::Subtree, action)
+}
+
+fn parse_domains<'a, F>(text: &'a str, rule: &mut F) -> Result<(), Cause>
+where
+    F: FnMut(&'a str, Scope, Action) -> Result<(), Cause>,
+{
+    let text = match text.find('#') {
+        Some(index) => text[..index].trim_end(),
+        None => text,
+    };
+    if text.is_empty() {
+        return Ok(());
+    }
+    if text.contains(char::is_whitespace) {
+        return Err(Kind::UnsupportedRule.at(0, text.len()));
+    }
+    if text.starts_with("*.") {
+        rule(&text[2..], Scope::Subtree, Action::Block)
+    } else {
+        rule(text, Scope::Exact, Action::Block)
+    }
+}
+
+/// Checks hosts against the rules of one or more lists
+///
+/// Allow rules win over block rules. Otherwise the most specific rule
+/// decides, and later rules replace earlier ones for the same name and
+/// scope.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Matcher<'a> {
+    rules: Vec<Rule<'a>>,
+    block: DomainMap<usize>,
+    allow: DomainMap<usize>,
+}
+
+impl<'a> Matcher<'a> {
+    /// Creates a matcher for the given rules
+    pub fn new<I: IntoIterator<Item = Rule<'a>>>(rules: I) -> Self {
+        let mut matcher = Self::default();
+        matcher.extend(rules);
+        matcher
+    }
+
+    /// The rule that decides what happens to `host`, if any
+    ///
+    /// The host is not checked, so anything that is not a name simply
+    /// matches no rules.
+    pub fn check(&self, host: &str) -> Option<&Rule<'a>> {
+        let found = self.allow.find(host).or_else(|| self.block.find(host))?;
+        self.rules.get(*found.value())
+    }
+
+    /// Whether `host` is blocked
+    pub fn is_blocked(&self, host: &str) -> bool {
+        match self.check(host) {
+            Some(rule) => rule.action == Action::Block,
+            None => false,
+        }
+    }
+
+    /// The rules, in the order they were added
+    pub fn rules(&self) -> &[Rule<'a>] {
+        &self.rules
+    }
+}
+
+impl<'a> Extend<Rule<'a>> for Matcher<'a> {
+    fn extend<I: IntoIterator<Item = Rule<'a>>>(&mut self, rules: I) {
+        for rule in rules {
+            let map = match rule.action {
+                Action::Block => &mut self.block,
+                Action::Allow => &mut self.allow,
+            };
+            map.insert_with(rule.name.as_str(), rule.scope, self.rules.len());
+            self.rules.push(rule);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use psl::List;
+
+    #[test]
+    fn hosts() {
+        let input = "
+            # blocked hosts
+            127.0.0.1 localhost
+            ::1 localhost ip6-localhost
+            0.0.0.0 ads.example.com tracker.example.com # trackers
+            0.0.0.0 _dmarc.example.com
+            ads.example.net
+            deadbeef.cafe ads.example.org
+            fe80::1%lo0 ads.example.io
+        ";
+        let (rules, errors) = parse(&List, input, Format::Hosts);
+        let names = rules
+            .iter()
+            .map(|rule| rule.name().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            names,
+            [
+                "ads.example.com",
+                "tracker.example.com",
+                "_dmarc.example.com",
+                "ads.example.io",
+            ]
+        );
+        assert_eq!(rules[0].line(), 5);
+        assert!(rules.iter().all(|rule| rule.scope() == Scope::Exact));
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!(errors[0].line(), 7);
+        assert_eq!(errors[0].error().kind(), Kind::UnsupportedRule);
+        assert_eq!(errors[0].error().span(), Some(0..15));
+        assert_eq!(errors[1].line(), 8);
+        assert_eq!(errors[1].error().span(), Some(0..13));
+
+        for address in &[
+            "0.0.0.0",
+            "::",
+            "::1",
+            "2001:db8::1",
+            "::ffff:127.0.0.1",
+            "fe80::1%lo0",
+            "2001:db8:0:0:0:0:2:1",
+            "1:2:3:4:5:6:1.2.3.4",
+        ] {
+            assert!(is_address(address), "{}", address);
+        }
+        for field in &[
+            "deadbeef.cafe",
+            "1.2.3",
+            "1.2.3.256",
+            "1.2.3.4.5",
+            "a.b.c.d",
+            ":",
+            "abc:def",
+            ":::",
+            "1::2::3",
+            "12345::1",
+            "1:2:3:4:5:6:7:8:9",
+            "1:2:3:4:5:6:7::8",
+            "1.2.3.4::1",
+            "::1%",
+        ] {
+            assert!(!is_address(field), "{}", field);
+        }
+
+        let matcher = Matcher::new(rules);
+        assert!(matcher.is_blocked("ads.example.com"));
+        assert!(!matcher.is_blocked("www.ads.example.com"));
+        assert!(!matcher.is_blocked("localhost"));
+    }
+
+    #[test]
+    fn adblock() {
+        let input = "[Adblock Plus 2.0]
+            ! Title: test
+            ||ads.example.com^
+            @@||good.ads.example.com^
+            ||tracker.example.com^$third-party
+            ||-bad.example.com^
+            /banner/*
+            ||example.org^";
+        let (rules, errors) = parse(&List, input, Format::Adblock);
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules[1].action(), Action::Allow);
+        assert_eq!(rules[1].name().as_str(), "good.ads.example.com");
+
+        let errors = errors
+            .iter()
+            .map(|error| (error.line(), error.error().kind(), error.error().span()))
+            .collect::<Vec<_>>();
+        assert_eq!(
+            errors,
+            [
+                (5, Kind::UnsupportedRule, Some(0..34)),
+                (6, Kind::LabelStartNotAlnum, Some(2..3)),
+                (7, Kind::UnsupportedRule, Some(0..9)),
+            ]
+        );
+
+        let matcher = Matcher::new(rules);
+        assert_eq!(matcher.check("x.ads.example.com").unwrap().line(), 3);
+        assert_eq!(
+            matcher.check("x.good.ads.example.com").unwrap().action(),
+            Action::Allow
+        );
+        assert!(matcher.is_blocked("Example.ORG"));
+        assert!(matcher.check("example.net").is_none());
+    }
+
+    #[test]
+    fn domains() {
+        let input = "
+            # one per line
+            ads.example.com
+            *.tracker.example.com # and subdomains
+            two names.example.com
+        ";
+        let (rules, errors) = parse(&List, input, Format::Domains);
+        assert_eq!(rules.len(), 2);
+        assert_eq!(rules[1].scope(), Scope::Subtree);
+        assert_eq!(rules[1].text(), "*.tracker.example.com # and subdomains");
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].line(), 5);
+        assert_eq!(
+            errors[0].to_string(),
+            "line 5: 'two names.example.com' is not a supported blocklist rule (bytes 0..21)"
+        );
+
+        let mut matcher = Matcher::new(rules);
+        assert!(matcher.is_blocked("a.tracker.example.com"));
+        assert!(!matcher.is_blocked("a.ads.example.com"));
+
+        // lists can be combined
+        let (allowed, _) = parse(&List, "@@||b.tracker.example.com^", Format::Adblock);
+        matcher.extend(allowed);
+        assert!(!matcher.is_blocked("b.tracker.example.com"));
+        assert_eq!(matcher.rules().len(), 3);
+    }
+}
@@ -0,0 +1,178 @@
+//! Compiling a public suffix list into Rust code
+//!
+//! This is meant for build scripts that pin a snapshot of
+//! `public_suffix_list.dat` instead of using the data shipped with the
+//! `psl` crate. The generated code defines a `LIST` static of type
+//! `addr::list::Compiled`, which needs neither `std` nor `alloc`, so the
+//! crate being built can use `addr` with `default-features = false`.
+//!
+//! Lists can also be compiled to the binary format read by
+//! `list::Binary`, for lists that are loaded at runtime but should not be
+//! parsed on every start.
+//!
+//! ```rust,no_run
+//! // build.rs
+//! use std::{env, path::Path};
+//!
+//! fn main() {
+//!     let out = Path::new(&env::var("OUT_DIR").unwrap()).join("psl.rs");
+//!     addr::build::compile_file("public_suffix_list.dat", out).unwrap();
+//!     println!("cargo:rerun-if-changed=public_suffix_list.dat");
+//! }
+//! ```
+
+use crate::list::{Entry, Rules};
+use core::fmt::Write;
+use psl_types::Type;
+use std::{fs, io, path::Path, string::String};
+
+/// Compiles a list in the format of `public_suffix_list.dat`
+///
+/// Returns the Rust source of the compiled list. Errors refer to the line
+/// of the invalid rule.
+pub fn compile(input: &str) -> crate::Result<'_, String> {
+    let rules = Rules::parse(input)?;
+    let metadata = rules.metadata();
+    let mut out = String::new();
+    // writing to a `String` never fails
+    let _ = write_source(
+        &mut out,
+        &rules.entries(),
+        metadata.version(),
+        metadata.commit(),
+    );
+    Ok(out)
+}
+
+/// Compiles the list in `input` and writes its Rust source to `output`
+///
+/// An invalid list is reported as `InvalidData`, wrapping the
+/// `OwnedError` that describes it.
+pub fn compile_file<P, Q>(input: P, output: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let input = fs::read_to_string(input)?;
+    let source = compile(&input)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.into_owned()))?;
+    fs::write(output, source)
+}
+
+/// Compiles the list in `input` to the format read by `list::Binary`
+///
+/// Errors are reported like those of `compile_file`.
+pub fn compile_binary_file<P, Q>(input: P, output: Q) -> io::Result<()>
+where
+    P: AsRef<Path>,
+    Q: AsRef<Path>,
+{
+    let input = fs::read_to_string(input)?;
+    let rules = Rules::parse(&input)
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.into_owned()))?;
+    let bytes = rules
+        .to_bytes()
+        .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.into_owned()))?;
+    fs::write(output, bytes)
+}
+
+fn write_source(
+    out: &mut String,
+    entries: &[Entry<'_>],
+    version: Option<&str>,
+    commit: Option<&str>,
+) -> core::fmt::Result {
+    writeln!(
+        out,
+        "// This file is @generated by `addr::build`. Do not edit."
+    )?;
+    writeln!(out)?;
+    writeln!(out, "/// The compiled public suffix list")?;
+    writeln!(
+        out,
+        "pub static LIST: ::addr::list::Compiled<'static> = ::addr::list::Compiled::new("
+    )?;
+    writeln!(out, "    &[")?;
+    for entry in entries {
+        writeln!(out, "        {},", Source(entry))?;
+    }
+    writeln!(out, "    ],")?;
+    writeln!(
+        out,
+        "    ::addr::list::Metadata::from_parts({:?}, {:?}),",
+        version, commit
+    )?;
+    writeln!(out, ");")
+}
+
+// formats an entry as the expression that creates it
+struct Source<'a, 'b>(&'a Entry<'b>);
+
+impl core::fmt::Display for Source<'_, '_> {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        let entry = self.0;
+        let constructor = match entry.typ() {
+            None => "branch",
+            Some(Type::Icann) => "icann",
+            Some(Type::Private) => "private",
+        };
+        write!(f, "::addr::list::Entry::{}(b\"", constructor)?;
+        for &byte in entry.label() {
+            match byte {
+                b'"' | b'\\' => write!(f, "\\x{:02x}", byte)?,
+                0x20..=0x7e => f.write_char(byte as char)?,
+                _ => write!(f, "\\x{:02x}", byte)?,
+            }
+        }
+        write!(f, "\", {}, {})", entry.first(), entry.count())?;
+        if entry.is_exception() {
+            f.write_str(".exception()")?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::compile;
+    use crate::error::Kind;
+
+    #[test]
+    fn source() {
+        let source = compile(
+            "// VERSION: 2024-06-25_10-05-23_UTC
+            // ===BEGIN ICANN DOMAINS===
+            uk
+            co.uk
+            *.ck
+            !www.ck
+            中国
+            // ===BEGIN PRIVATE DOMAINS===
+            github.io",
+        )
+        .unwrap();
+        let expected = r#"// This file is @generated by `addr::build`. Do not edit.
+
+/// The compiled public suffix list
+pub static LIST: ::addr::list::Compiled<'static> = ::addr::list::Compiled::new(
+    &[
+        ::addr::list::Entry::branch(b"", 1, 5),
+        ::addr::list::Entry::branch(b"ck", 6, 2),
+        ::addr::list::Entry::branch(b"io", 8, 1),
+        ::addr::list::Entry::icann(b"uk", 9, 1),
+        ::addr::list::Entry::icann(b"xn--fiqs8s", 10, 0),
+        ::addr::list::Entry::icann(b"\xe4\xb8\xad\xe5\x9b\xbd", 10, 0),
+        ::addr::list::Entry::icann(b"*", 10, 0),
+        ::addr::list::Entry::icann(b"www", 10, 0).exception(),
+        ::addr::list::Entry::private(b"github", 10, 0),
+        ::addr::list::Entry::icann(b"co", 10, 0),
+    ],
+    ::addr::list::Metadata::from_parts(Some("2024-06-25_10-05-23_UTC"), None),
+);
+"#;
+        assert_eq!(source, expected);
+
+        let error = compile("uk\nco..uk").unwrap_err();
+        assert_eq!(error.kind(), Kind::EmptyLabel);
+    }
+}
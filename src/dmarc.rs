@@ -0,0 +1,446 @@
+//! DMARC organizational domains and identifier alignment
+//!
+//! See [RFC 7489](https://tools.ietf.org/html/rfc7489)
+
+use crate::domain::Name;
+use crate::error::{Kind, Result};
+
+/// The organizational domain of a domain name
+///
+/// This is the registrable part of the name as computed using the Public
+/// Suffix List. If the name is itself a public suffix, the name is returned
+/// as is. Any trailing dot is removed.
+pub fn organizational_domain<'a>(name: &Name<'a>) -> &'a str {
+    trim(name.root().unwrap_or_else(|| name.as_str()))
+}
+
+/// How well an authenticated identifier aligns with the RFC5322.From domain
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Alignment {
+    /// The organizational domains differ
+    Unaligned,
+    /// The organizational domains are the same
+    Relaxed,
+    /// The domains are exactly the same
+    Strict,
+}
+
+impl Alignment {
+    /// Checks the alignment between the `from` domain and an authenticated one
+    pub fn of(from: &Name<'_>, authenticated: &Name<'_>) -> Self {
+        if eq(trim(from.as_str()), trim(authenticated.as_str())) {
+            Alignment::Strict
+        } else if eq(
+            organizational_domain(from),
+            organizational_domain(authenticated),
+        ) {
+            Alignment::Relaxed
+        } else {
+            Alignment::Unaligned
+        }
+    }
+
+    /// Whether this alignment passes the given mode
+    pub fn satisfies(self, mode: Mode) -> bool {
+        match mode {
+            Mode::Strict => self == Alignment::Strict,
+            Mode::Relaxed => self != Alignment::Unaligned,
+        }
+    }
+}
+
+/// The result of checking SPF and DKIM identifier alignment
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Report<'a> {
+    organizational_domain: &'a str,
+    spf: Option<Alignment>,
+    dkim: Option<Alignment>,
+}
+
+impl<'a> Report<'a> {
+    /// Checks the alignment of the SPF and DKIM authenticated domains
+    ///
+    /// `spf` is the domain that passed SPF, if any, and `dkim` holds the `d=`
+    /// domains of all the DKIM signatures that passed verification.
+    pub fn new(from: &Name<'a>, spf: Option<&Name<'_>>, dkim: &[Name<'_>]) -> Self {
+        Self {
+            organizational_domain: organizational_domain(from),
+            spf: spf.map(|spf| Alignment::of(from, spf)),
+            dkim: dkim.iter().map(|dkim| Alignment::of(from, dkim)).max(),
+        }
+    }
+
+    /// The organizational domain of the RFC5322.From domain
+    pub const fn organizational_domain(&self) -> &'a str {
+        self.organizational_domain
+    }
+
+    /// The alignment of the SPF authenticated domain
+    pub const fn spf(&self) -> Option<Alignment> {
+        self.spf
+    }
+
+    /// The best alignment among the DKIM authenticated domains
+    pub const fn dkim(&self) -> Option<Alignment> {
+        self.dkim
+    }
+
+    /// Whether either identifier is aligned according to the record
+    pub fn passes(&self, record: &Record<'_>) -> bool {
+        let spf = self.spf.map(|spf| spf.satisfies(record.aspf()));
+        let dkim = self.dkim.map(|dkim| dkim.satisfies(record.adkim()));
+        spf.unwrap_or_default() || dkim.unwrap_or_default()
+    }
+}
+
+/// Identifier alignment mode (`adkim` and `aspf` tags)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Mode {
+    Relaxed,
+    Strict,
+}
+
+/// Requested mail receiver policy (`p` and `sp` tags)
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Policy {
+    None,
+    Quarantine,
+    Reject,
+}
+
+/// A parsed `_dmarc` TXT record
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Record<'a> {
+    policy: Policy,
+    subdomain_policy: Option<Policy>,
+    adkim: Mode,
+    aspf: Mode,
+    percent: u8,
+    interval: u32,
+    rua: Option<&'a str>,
+    ruf: Option<&'a str>,
+    fo: Option<&'a str>,
+    rf: Option<&'a str>,
+}
+
+impl<'a> Record<'a> {
+    pub(crate) fn parse(input: &'a str) -> Result<Record<'a>> {
+        let mut tags = input
+            .split(';')
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(split_tag);
+
+        match tags.next() {
+            // the version is case-sensitive
+            Some(Ok((name, value))) if name == "v" && value == "DMARC1" => {}
+            _ => return Err(Kind::InvalidDmarcRecord.into()),
+        }
+
+        let mut policy = None;
+        let mut valid_subdomain_policy = true;
+        let mut record = Self {
+            policy: Policy::None,
+            subdomain_policy: None,
+            adkim: Mode::Relaxed,
+            aspf: Mode::Relaxed,
+            percent: 100,
+            interval: 86400,
+            rua: None,
+            ruf: None,
+            fo: None,
+            rf: None,
+        };
+
+        // invalid values of optional tags are ignored and the defaults kept
+        for tag in tags {
+            let (name, value) = tag?;
+            if eq(name, "p") {
+                policy = parse_policy(value);
+            } else if eq(name, "sp") {
+                record.subdomain_policy = parse_policy(value);
+                valid_subdomain_policy = record.subdomain_policy.is_some();
+            } else if eq(name, "adkim") {
+                record.adkim = parse_mode(value).unwrap_or(record.adkim);
+            } else if eq(name, "aspf") {
+                record.aspf = parse_mode(value).unwrap_or(record.aspf);
+            } else if eq(name, "pct") {
+                if let Ok(percent) = value.parse() {
+                    if percent <= 100 {
+                        record.percent = percent;
+                    }
+                }
+            } else if eq(name, "ri") {
+                record.interval = value.parse().unwrap_or(record.interval);
+            } else if eq(name, "rua") {
+                record.rua = Some(value);
+            } else if eq(name, "ruf") {
+                record.ruf = Some(value);
+            } else if eq(name, "fo") {
+                record.fo = Some(value).filter(|value| is_list(value, is_failure_option));
+            } else if eq(name, "rf") {
+                record.rf = Some(value).filter(|value| is_list(value, is_keyword));
+            }
+            // unknown tags must be ignored
+        }
+
+        // a missing or invalid `p`, or an invalid `sp`, is read as `p=none`
+        // if there is somewhere to send reports to
+        // https://tools.ietf.org/html/rfc7489#section-6.6.3
+        match policy {
+            Some(policy) if valid_subdomain_policy => record.policy = policy,
+            _ if record.rua.map_or(false, has_report_uri) => {
+                record.policy = Policy::None;
+                record.subdomain_policy = None;
+            }
+            _ => return Err(Kind::InvalidDmarcRecord.into()),
+        }
+
+        Ok(record)
+    }
+
+    /// The policy for the domain itself (`p`)
+    ///
+    /// Records with a missing or invalid `p`, or an invalid `sp`, are only
+    /// accepted if `rua` holds a valid URI, and then have a policy of
+    /// `none`, as RFC 7489 section 6.6.3 requires.
+    pub const fn policy(&self) -> Policy {
+        self.policy
+    }
+
+    /// The policy for subdomains (`sp`), falling back to `p`
+    pub fn subdomain_policy(&self) -> Policy {
+        self.subdomain_policy.unwrap_or(self.policy)
+    }
+
+    /// The DKIM identifier alignment mode (`adkim`)
+    pub const fn adkim(&self) -> Mode {
+        self.adkim
+    }
+
+    /// The SPF identifier alignment mode (`aspf`)
+    pub const fn aspf(&self) -> Mode {
+        self.aspf
+    }
+
+    /// The percentage of messages the policy applies to (`pct`)
+    pub const fn percent(&self) -> u8 {
+        self.percent
+    }
+
+    /// The aggregate reporting interval in seconds (`ri`)
+    pub const fn interval(&self) -> u32 {
+        self.interval
+    }
+
+    /// The raw aggregate report URIs (`rua`)
+    pub const fn rua(&self) -> Option<&'a str> {
+        self.rua
+    }
+
+    /// The raw failure report URIs (`ruf`)
+    pub const fn ruf(&self) -> Option<&'a str> {
+        self.ruf
+    }
+
+    /// The raw failure reporting options (`fo`)
+    ///
+    /// `None` means the default, `0`.
+    pub const fn fo(&self) -> Option<&'a str> {
+        self.fo
+    }
+
+    /// The raw failure report formats (`rf`)
+    ///
+    /// `None` means the default, `afrf`.
+    pub const fn rf(&self) -> Option<&'a str> {
+        self.rf
+    }
+}
+
+/// Parses the contents of a `_dmarc` TXT record
+pub fn parse_record(input: &str) -> crate::Result<'_, Record<'_>> {
+    Record::parse(input).map_err(|cause| cause.error_with(input))
+}
+
+fn split_tag(tag: &str) -> Result<(&str, &str)> {
+    let mut parts = tag.splitn(2, '=');
+    let name = parts.next().unwrap_or_default().trim();
+    let value = parts.next().ok_or(Kind::InvalidDmarcRecord)?.trim();
+    Ok((name, value))
+}
+
+fn parse_policy(value: &str) -> Option<Policy> {
+    if value.eq_ignore_ascii_case("none") {
+        Some(Policy::None)
+    } else if value.eq_ignore_ascii_case("quarantine") {
+        Some(Policy::Quarantine)
+    } else if value.eq_ignore_ascii_case("reject") {
+        Some(Policy::Reject)
+    } else {
+        None
+    }
+}
+
+fn parse_mode(value: &str) -> Option<Mode> {
+    if value.eq_ignore_ascii_case("r") {
+        Some(Mode::Relaxed)
+    } else if value.eq_ignore_ascii_case("s") {
+        Some(Mode::Strict)
+    } else {
+        None
+    }
+}
+
+// whether a comma-separated list of report URIs, each optionally followed
+// by `!` and a size limit, holds at least one valid URI
+fn has_report_uri(value: &str) -> bool {
+    value.split(',').any(|uri| {
+        let uri = uri.trim().split('!').next().unwrap_or_default();
+        is_uri(uri)
+    })
+}
+
+// a scheme followed by `:` and the rest of the URI, without whitespace
+// https://tools.ietf.org/html/rfc3986#section-3.1
+fn is_uri(uri: &str) -> bool {
+    let mut parts = uri.splitn(2, ':');
+    let scheme = parts.next().unwrap_or_default();
+    let rest = match parts.next() {
+        Some(rest) => rest,
+        None => return false,
+    };
+    let valid_scheme = scheme
+        .bytes()
+        .next()
+        .map_or(false, |b| b.is_ascii_alphabetic())
+        && scheme
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'+' || b == b'-' || b == b'.');
+    valid_scheme && !rest.is_empty() && rest.bytes().all(|b| b.is_ascii_graphic())
+}
+
+// a colon-separated list of values
+fn is_list(value: &str, is_valid: fn(&str) -> bool) -> bool {
+    value.split(':').map(str::trim).all(is_valid)
+}
+
+fn is_failure_option(option: &str) -> bool {
+    ["0", "1", "d", "s"].iter().any(|x| eq(option, x))
+}
+
+fn is_keyword(format: &str) -> bool {
+    !format.is_empty() && format.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn trim(name: &str) -> &str {
+    if name.ends_with('.') {
+        name.get(..name.len() - 1).unwrap_or_default()
+    } else {
+        name
+    }
+}
+
+fn eq(a: &str, b: &str) -> bool {
+    a.eq_ignore_ascii_case(b)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::DomainName;
+    use psl::List;
+
+    #[test]
+    fn organizational() {
+        let name = List.parse_domain_name("mail.example.co.uk.").unwrap();
+        assert_eq!(organizational_domain(&name), "example.co.uk");
+        let name = List.parse_domain_name("co.uk").unwrap();
+        assert_eq!(organizational_domain(&name), "co.uk");
+    }
+
+    #[test]
+    fn alignment() {
+        let from = List.parse_domain_name("news.example.com").unwrap();
+        let same = List.parse_domain_name("NEWS.example.com.").unwrap();
+        let sibling = List.parse_domain_name("bounces.example.com").unwrap();
+        let other = List.parse_domain_name("example.net").unwrap();
+        assert_eq!(Alignment::of(&from, &same), Alignment::Strict);
+        assert_eq!(Alignment::of(&from, &sibling), Alignment::Relaxed);
+        assert_eq!(Alignment::of(&from, &other), Alignment::Unaligned);
+
+        let report = Report::new(&from, Some(&other), &[other, sibling]);
+        assert_eq!(report.organizational_domain(), "example.com");
+        assert_eq!(report.spf(), Some(Alignment::Unaligned));
+        assert_eq!(report.dkim(), Some(Alignment::Relaxed));
+
+        let record = parse_record("v=DMARC1; p=reject").unwrap();
+        assert!(report.passes(&record));
+        let record = parse_record("v=DMARC1; p=reject; adkim=s").unwrap();
+        assert!(!report.passes(&record));
+    }
+
+    #[test]
+    fn record() {
+        let record = parse_record(
+            "v=DMARC1; p=quarantine; sp=reject; adkim=s; pct=50; rua=mailto:d@example.com; x=y;",
+        )
+        .unwrap();
+        assert_eq!(record.policy(), Policy::Quarantine);
+        assert_eq!(record.subdomain_policy(), Policy::Reject);
+        assert_eq!(record.adkim(), Mode::Strict);
+        assert_eq!(record.aspf(), Mode::Relaxed);
+        assert_eq!(record.percent(), 50);
+        assert_eq!(record.rua(), Some("mailto:d@example.com"));
+
+        let record = parse_record("v=DMARC1;p=none").unwrap();
+        assert_eq!(record.subdomain_policy(), Policy::None);
+
+        parse_record("p=none; v=DMARC1").unwrap_err();
+        parse_record("v=DMARC1").unwrap_err();
+        parse_record("v=DMARC1; p=block").unwrap_err();
+        parse_record("v=dmarc1; p=none").unwrap_err();
+        parse_record("v=spf1 -all").unwrap_err();
+    }
+
+    #[test]
+    fn invalid_optional_tags() {
+        let record =
+            parse_record("v=DMARC1; p=reject; adkim=x; aspf=; pct=101; ri=daily; fo=2; rf=a f")
+                .unwrap();
+        assert_eq!(record.policy(), Policy::Reject);
+        assert_eq!(record.subdomain_policy(), Policy::Reject);
+        assert_eq!(record.adkim(), Mode::Relaxed);
+        assert_eq!(record.aspf(), Mode::Relaxed);
+        assert_eq!(record.percent(), 100);
+        assert_eq!(record.interval(), 86400);
+        assert_eq!(record.fo(), None);
+        assert_eq!(record.rf(), None);
+
+        let record = parse_record("v=DMARC1; p=none; pct=0; ri=3600; fo=d:S; rf=afrf").unwrap();
+        assert_eq!(record.percent(), 0);
+        assert_eq!(record.interval(), 3600);
+        assert_eq!(record.fo(), Some("d:S"));
+        assert_eq!(record.rf(), Some("afrf"));
+    }
+
+    #[test]
+    fn policy_fallback() {
+        let rua = "rua=mailto:d@example.com";
+        for tags in &["p=block; sp=reject", "sp=reject", "p=reject; sp=bogus"] {
+            let input = format!("v=DMARC1; {}; {}", tags, rua);
+            let record = parse_record(&input).unwrap();
+            assert_eq!(record.policy(), Policy::None, "{}", input);
+            assert_eq!(record.subdomain_policy(), Policy::None, "{}", input);
+            assert_eq!(record.rua(), Some("mailto:d@example.com"));
+
+            parse_record(&format!("v=DMARC1; {}", tags)).unwrap_err();
+        }
+
+        let record = parse_record("v=DMARC1; rua=bogus, mailto:d@example.com!10m").unwrap();
+        assert_eq!(record.policy(), Policy::None);
+        parse_record("v=DMARC1; p=block; rua=bogus").unwrap_err();
+        parse_record("v=DMARC1; p=block; rua=mailto:").unwrap_err();
+        parse_record("v=DMARC1; p=block; rua=1x:y").unwrap_err();
+    }
+}
@@ -1,6 +1,6 @@
 //! DNS types
 
-use crate::error::Result;
+use crate::error::{FailFast, Result, Sink};
 use crate::matcher;
 use core::{fmt, str};
 use psl_types::{List, Suffix, Type};
@@ -14,7 +14,15 @@ pub struct Name<'a> {
 
 impl<'a> Name<'a> {
     pub(crate) fn parse<T: List + ?Sized>(list: &T, name: &'a str) -> Result<Name<'a>> {
-        matcher::is_dns_name(name)?;
+        Self::check(list, name, &mut FailFast)
+    }
+
+    pub(crate) fn check<T, S>(list: &T, name: &'a str, sink: &mut S) -> Result<Name<'a>>
+    where
+        T: List + ?Sized,
+        S: Sink + ?Sized,
+    {
+        matcher::check_dns_name(name, sink)?;
         Ok(Self {
             full: name,
             suffix: list.suffix(name.as_bytes()),
@@ -1,7 +1,9 @@
 //! Domain name types
 
-use crate::error::{Kind, Result};
+use crate::error::{Adjust, FailFast, Kind, Result, Sink};
 use crate::matcher;
+use crate::policy::Policy;
+use crate::punycode;
 use core::fmt;
 use psl_types::{List, Type};
 
@@ -14,20 +16,42 @@ pub struct Name<'a> {
 
 impl<'a> Name<'a> {
     pub(crate) fn parse<T: List + ?Sized>(list: &T, name: &'a str) -> Result<Name<'a>> {
+        Self::check(list, name, &Policy::new(), &mut FailFast)
+    }
+
+    pub(crate) fn check<T, S>(
+        list: &T,
+        name: &'a str,
+        policy: &Policy,
+        sink: &mut S,
+    ) -> Result<Name<'a>>
+    where
+        T: List + ?Sized,
+        S: Sink + ?Sized,
+    {
         let stripped = if name.ends_with('.') {
             name.get(..name.len() - 1).unwrap_or_default()
         } else {
             name
         };
         if stripped.contains('.') {
-            matcher::is_domain_name(stripped)?;
+            matcher::check_domain_name(stripped, policy, sink)?;
         } else {
-            matcher::is_label(stripped, true)?;
+            let mut sink = Adjust::new(sink, |cause| cause.in_label(0));
+            matcher::check_label(stripped, true, policy, &mut sink)?;
+        }
+        let suffix = match list.suffix(name.as_bytes()) {
+            Some(suffix) => suffix,
+            None => return sink.fail(Kind::InvalidDomain.at(0, name.len())),
+        };
+        let offset = name.len() - suffix.as_bytes().len();
+        if policy.require_known_suffix && !suffix.is_known() {
+            sink.report(Kind::UnknownSuffix.at(offset, name.len()))?;
+        }
+        if policy.reject_private && suffix.typ() == Some(Type::Private) {
+            sink.report(Kind::PrivateSuffix.at(offset, name.len()))?;
         }
-        Ok(Self {
-            suffix: list.suffix(name.as_bytes()).ok_or(Kind::InvalidDomain)?,
-            full: name,
-        })
+        Ok(Self { suffix, full: name })
     }
 
     /// Full domain name as a `str`
@@ -70,6 +94,26 @@ impl<'a> Name<'a> {
         &self.full[offset..]
     }
 
+    /// The length of the name in DNS wire format
+    ///
+    /// IDN labels are measured as A-labels (punycode). This is the number of
+    /// octets a resolver sees. It is at most 255 for names checked with
+    /// `Policy::wire_lengths(true)`, the default; with it off, IDNs are only
+    /// limited by their length as written and can be longer.
+    pub fn wire_len(&self) -> usize {
+        let name = if self.full.ends_with('.') {
+            self.full.get(..self.full.len() - 1).unwrap_or_default()
+        } else {
+            self.full
+        };
+        let labels = name
+            .split('.')
+            .map(|label| 1 + punycode::ascii_len(label).unwrap_or(label.len()))
+            .sum::<usize>();
+        // the root label
+        labels + 1
+    }
+
     /// Whether the suffix of the domain name is in the Public Suffix List
     pub fn has_known_suffix(&self) -> bool {
         self.suffix.is_known()
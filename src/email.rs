@@ -1,11 +1,14 @@
 //! Email address types
 
 use crate::domain::Name;
-use crate::error::{Kind, Result};
+use crate::error::{Adjust, FailFast, Kind, Result, Sink};
 use crate::matcher;
 #[cfg(feature = "net")]
 #[cfg(not(feature = "std"))]
 use crate::net::IpAddr;
+use crate::policy::Policy;
+#[cfg(feature = "alloc")]
+use alloc::string::String;
 use core::fmt;
 #[cfg(not(any(feature = "net", feature = "std")))]
 use core::str::FromStr;
@@ -13,6 +16,9 @@ use psl_types::List;
 #[cfg(feature = "std")]
 use std::net::IpAddr;
 
+/// The separator used by subaddresses (plus addressing) by default
+pub const TAG_SEPARATOR: char = '+';
+
 /// Holds information about a particular email address
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Address<'a> {
@@ -23,14 +29,26 @@ pub struct Address<'a> {
 
 impl<'a> Address<'a> {
     pub(crate) fn parse<T: List + ?Sized>(list: &T, address: &'a str) -> Result<Address<'a>> {
+        Self::check(list, address, &mut FailFast)
+    }
+
+    pub(crate) fn check<T, S>(list: &T, address: &'a str, sink: &mut S) -> Result<Address<'a>>
+    where
+        T: List + ?Sized,
+        S: Sink + ?Sized,
+    {
         if address.chars().count() > 254 {
-            return Err(Kind::EmailTooLong);
+            sink.report(Kind::EmailTooLong.at(0, address.len()))?;
         }
-        let at_sign = address.rfind('@').ok_or(Kind::NoAtSign)?;
-        let local = address.get(..at_sign).ok_or(Kind::NoUserPart)?;
-        matcher::is_email_local(local)?;
-        let rest = address.get(at_sign + 1..).ok_or(Kind::NoHostPart)?;
-        let host = Host::parse(list, rest)?;
+        let at_sign = match address.rfind('@') {
+            Some(at_sign) => at_sign,
+            None => return sink.fail(Kind::NoAtSign.into()),
+        };
+        let local = &address[..at_sign];
+        matcher::check_email_local(local, sink)?;
+        let rest = &address[at_sign + 1..];
+        let mut sink = Adjust::new(sink, |cause| cause.shift(at_sign + 1));
+        let host = Host::check(list, rest, &mut sink)?;
         Ok(Self {
             host,
             at_sign,
@@ -52,6 +70,74 @@ impl<'a> Address<'a> {
     pub fn user(&self) -> &'a str {
         &self.full[..self.at_sign]
     }
+
+    /// The user part without its subaddress tag
+    ///
+    /// For `john+newsletter@example.com` this is `john`.
+    pub fn base_user(&self) -> &'a str {
+        self.base_user_with(TAG_SEPARATOR)
+    }
+
+    /// The user part without its subaddress tag, using a custom separator
+    pub fn base_user_with(&self, separator: char) -> &'a str {
+        let user = self.user();
+        match self.tag_offset(separator) {
+            Some(offset) => &user[..offset],
+            None => user,
+        }
+    }
+
+    /// The subaddress tag, if any
+    ///
+    /// For `john+newsletter@example.com` this is `newsletter`. Quoted user
+    /// parts never have a tag.
+    pub fn tag(&self) -> Option<&'a str> {
+        self.tag_with(TAG_SEPARATOR)
+    }
+
+    /// The subaddress tag, if any, using a custom separator
+    pub fn tag_with(&self, separator: char) -> Option<&'a str> {
+        let offset = self.tag_offset(separator)?;
+        self.user().get(offset + separator.len_utf8()..)
+    }
+
+    fn tag_offset(&self, separator: char) -> Option<usize> {
+        let user = self.user();
+        if user.starts_with('"') {
+            return None;
+        }
+        user.find(separator)
+    }
+
+    /// Rebuilds this address with a different subaddress tag
+    ///
+    /// Passing `None` removes the tag. Returns `None` if the user part is
+    /// quoted, since a tag can't be attached to it. The result is not
+    /// validated so it should be parsed again if `tag` is untrusted.
+    #[cfg(feature = "alloc")]
+    pub fn with_tag(&self, tag: Option<&str>) -> Option<String> {
+        self.with_tag_using(TAG_SEPARATOR, tag)
+    }
+
+    /// Rebuilds this address with a different subaddress tag, using a custom separator
+    #[cfg(feature = "alloc")]
+    pub fn with_tag_using(&self, separator: char, tag: Option<&str>) -> Option<String> {
+        if self.user().starts_with('"') {
+            return None;
+        }
+        let base = self.base_user_with(separator);
+        let host = &self.full[self.at_sign..];
+        let tag_len = tag.map(|tag| separator.len_utf8() + tag.len());
+        let mut address =
+            String::with_capacity(base.len() + tag_len.unwrap_or_default() + host.len());
+        address.push_str(base);
+        if let Some(tag) = tag {
+            address.push(separator);
+            address.push_str(tag);
+        }
+        address.push_str(host);
+        Some(address)
+    }
 }
 
 impl fmt::Display for Address<'_> {
@@ -77,7 +163,7 @@ pub enum IpAddr {}
 impl FromStr for IpAddr {
     type Err = Kind;
 
-    fn from_str(_: &str) -> Result<Self> {
+    fn from_str(_: &str) -> core::result::Result<Self, Self::Err> {
         unreachable!()
     }
 }
@@ -90,22 +176,25 @@ pub enum Host<'a> {
 }
 
 impl<'a> Host<'a> {
-    pub(crate) fn parse<T: List + ?Sized>(list: &T, host: &'a str) -> Result<Host<'a>> {
+    pub(crate) fn check<T, S>(list: &T, host: &'a str, sink: &mut S) -> Result<Host<'a>>
+    where
+        T: List + ?Sized,
+        S: Sink + ?Sized,
+    {
         if host.starts_with('[') && host.ends_with(']') {
             let host_len = host.len();
             if host_len < 3 {
-                return Err(Kind::InvalidIpAddr);
+                return sink.fail(Kind::InvalidIpAddr.at(0, host_len));
             }
             if cfg!(not(any(feature = "net", feature = "std"))) {
-                return Err(Kind::NetDisabled);
+                return sink.fail(Kind::NetDisabled.at(0, host_len));
+            }
+            match host[1..host_len - 1].parse() {
+                Ok(ip_addr) => Ok(Host::IpAddr(ip_addr)),
+                Err(_) => sink.fail(Kind::InvalidIpAddr.at(1, host_len - 1)),
             }
-            let ip_addr = host
-                .get(1..host_len - 1)
-                .ok_or(Kind::InvalidIpAddr)?
-                .parse()?;
-            Ok(Host::IpAddr(ip_addr))
         } else {
-            Ok(Host::Domain(Name::parse(list, host)?))
+            Ok(Host::Domain(Name::check(list, host, &Policy::new(), sink)?))
         }
     }
 }
@@ -134,4 +223,36 @@ mod test {
         let email = Address::parse(&List, "johndoe@localhost").unwrap();
         assert_eq!(email.user(), "johndoe");
     }
+
+    #[test]
+    fn tag() {
+        let email = Address::parse(&List, "john+news+weekly@example.com").unwrap();
+        assert_eq!(email.base_user(), "john");
+        assert_eq!(email.tag(), Some("news+weekly"));
+
+        let email = Address::parse(&List, "john@example.com").unwrap();
+        assert_eq!(email.base_user(), "john");
+        assert_eq!(email.tag(), None);
+
+        let email = Address::parse(&List, "john-news@example.com").unwrap();
+        assert_eq!(email.base_user_with('-'), "john");
+        assert_eq!(email.tag_with('-'), Some("news"));
+
+        let email = Address::parse(&List, r#""john+news"@example.com"#).unwrap();
+        assert_eq!(email.base_user(), r#""john+news""#);
+        assert_eq!(email.tag(), None);
+    }
+
+    #[test]
+    fn with_tag() {
+        let email = Address::parse(&List, "john+news@example.com").unwrap();
+        assert_eq!(
+            email.with_tag(Some("billing")).as_deref(),
+            Some("john+billing@example.com")
+        );
+        assert_eq!(email.with_tag(None).as_deref(), Some("john@example.com"));
+
+        let email = Address::parse(&List, r#""john doe"@example.com"#).unwrap();
+        assert_eq!(email.with_tag(Some("news")), None);
+    }
 }
@@ -1,14 +1,19 @@
 //! The errors returned by this crate
 
+#[cfg(feature = "alloc")]
+use alloc::string::String;
 use core::fmt;
+use core::ops::Range;
 
-pub(crate) type Result<T> = core::result::Result<T, Kind>;
+pub(crate) type Result<T> = core::result::Result<T, Cause>;
 
 /// Information about the error and its input
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 pub struct Error<'a> {
     kind: Kind,
     input: &'a str,
+    span: Option<(usize, usize)>,
+    label: Option<usize>,
 }
 
 impl<'a> Error<'a> {
@@ -21,10 +26,24 @@ impl<'a> Error<'a> {
     pub const fn input(&self) -> &'a str {
         self.input
     }
-}
 
-impl<'a> fmt::Display for Error<'a> {
-    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+    /// The byte range of the offending character or label in the input
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.map(|(start, end)| start..end)
+    }
+
+    /// The index of the offending label, counting from the left
+    pub const fn label(&self) -> Option<usize> {
+        self.label
+    }
+
+    /// Copies the input so the error no longer borrows it
+    #[cfg(feature = "alloc")]
+    pub fn into_owned(self) -> OwnedError {
+        self.into()
+    }
+
+    fn describe(&self, f: &mut fmt::Formatter) -> fmt::Result {
         match self.kind {
             Kind::NameTooLong => write!(f, "'{}' is too long", self.input),
             Kind::NetDisabled => write!(f, "'{}'; can't parse email addresses containing IP addresses when `net` feature is disabled", self.input),
@@ -35,8 +54,17 @@ impl<'a> fmt::Display for Error<'a> {
             Kind::EmailTooLong => write!(f, "'{}' is too long for an email address", self.input),
             Kind::EmptyName => write!(f, "name is empty"),
             Kind::IllegalCharacter => write!(f, "'{}' contains an illegal character", self.input),
+            Kind::InvalidBinaryList => write!(f, "not a valid binary suffix list"),
+            Kind::InvalidDmarcRecord => write!(f, "'{}' is not a valid DMARC record", self.input),
             Kind::InvalidDomain => write!(f, "'{}' is not a valid domain name", self.input),
             Kind::InvalidIpAddr => write!(f, "'{}' contains an invalid IP address", self.input),
+            Kind::InvalidPunycode => {
+                write!(f, "'{}' has a label that is not valid punycode", self.input)
+            }
+            Kind::InvalidRule => {
+                write!(f, "'{}' is not a valid public suffix rule", self.input)
+            }
+            Kind::InvalidMailto => write!(f, "'{}' is not a valid mailto URI", self.input),
             Kind::LabelEndNotAlnum => {
                 write!(
                     f,
@@ -51,20 +79,132 @@ impl<'a> fmt::Display for Error<'a> {
                     self.input
                 )
             }
+            Kind::LabelStartNotAlpha => {
+                write!(
+                    f,
+                    "'{}' has a label that does not start with a letter",
+                    self.input
+                )
+            }
             Kind::LabelTooLong => write!(f, "'{}' has a label that is too long", self.input),
+            Kind::MetadataTooLong => {
+                write!(f, "'{}' is too long for a binary suffix list", self.input)
+            }
             Kind::NoAtSign => write!(f, "'{}' does not have an @ sign", self.input),
             Kind::NoHostPart => write!(f, "'{}' does not have a host part", self.input),
             Kind::NoUserPart => write!(f, "'{}' does not have a user local part", self.input),
             Kind::NumericTld => write!(f, "'{}' has a numeric TLD", self.input),
+            Kind::PrivateSuffix => write!(f, "'{}' has a private suffix", self.input),
+            Kind::ReservedLabel => write!(f, "'{}' has a reserved label", self.input),
             Kind::QuoteUnclosed => write!(f, "'{}' has an unclosed quotation mark", self.input),
             Kind::TooManyLabels => write!(f, "'{}' contains too many labels", self.input),
+            Kind::UnsupportedRule => {
+                write!(f, "'{}' is not a supported blocklist rule", self.input)
+            }
+            Kind::UnknownSuffix => write!(f, "'{}' does not have a known suffix", self.input),
         }
     }
 }
 
+/// The alternate form (`{:#}`) underlines the offending part of the input
+impl<'a> fmt::Display for Error<'a> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        self.describe(f)?;
+        let (start, end) = match self.span {
+            Some(span) => span,
+            None => return Ok(()),
+        };
+        match self.label {
+            Some(label) => write!(f, " (label {}, bytes {}..{})", label, start, end)?,
+            None => write!(f, " (bytes {}..{})", start, end)?,
+        }
+        // there is nothing to underline in binary input
+        if f.alternate() && !self.input.is_empty() {
+            let before = self.input.get(..start).unwrap_or_default();
+            let within = self.input.get(start..end).unwrap_or_default();
+            write!(f, "\n{}\n", self.input)?;
+            for _ in before.chars() {
+                f.write_str(" ")?;
+            }
+            for _ in 0..within.chars().count().max(1) {
+                f.write_str("^")?;
+            }
+        }
+        Ok(())
+    }
+}
+
 #[cfg(feature = "std")]
 impl<'a> std::error::Error for Error<'a> {}
 
+/// An error that owns its input
+///
+/// Unlike `Error`, this can outlive the input so it can be boxed
+/// into a `'static` error or sent to another thread.
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct OwnedError {
+    kind: Kind,
+    input: String,
+    span: Option<(usize, usize)>,
+    label: Option<usize>,
+}
+
+#[cfg(feature = "alloc")]
+impl OwnedError {
+    /// The kind of error this is
+    pub const fn kind(&self) -> Kind {
+        self.kind
+    }
+
+    /// The input that resulted in this error
+    pub fn input(&self) -> &str {
+        &self.input
+    }
+
+    /// The byte range of the offending character or label in the input
+    pub fn span(&self) -> Option<Range<usize>> {
+        self.span.map(|(start, end)| start..end)
+    }
+
+    /// The index of the offending label, counting from the left
+    pub const fn label(&self) -> Option<usize> {
+        self.label
+    }
+
+    /// Borrows this error as an `Error`
+    pub fn as_error(&self) -> Error<'_> {
+        Error {
+            kind: self.kind,
+            input: &self.input,
+            span: self.span,
+            label: self.label,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl From<Error<'_>> for OwnedError {
+    fn from(error: Error<'_>) -> Self {
+        Self {
+            kind: error.kind,
+            input: error.input.into(),
+            span: error.span,
+            label: error.label,
+        }
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for OwnedError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        fmt::Display::fmt(&self.as_error(), f)
+    }
+}
+
+#[cfg(feature = "std")]
+impl std::error::Error for OwnedError {}
+
 /// Description of the error
 #[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
 #[non_exhaustive]
@@ -76,22 +216,38 @@ pub enum Kind {
     EmailTooLong,
     EmptyName,
     IllegalCharacter,
+    InvalidBinaryList,
+    InvalidDmarcRecord,
     InvalidDomain,
     InvalidIpAddr,
+    InvalidMailto,
+    InvalidPunycode,
+    InvalidRule,
     LabelEndNotAlnum,
     LabelStartNotAlnum,
+    LabelStartNotAlpha,
     LabelTooLong,
+    MetadataTooLong,
     NoAtSign,
     NoHostPart,
     NoUserPart,
     NumericTld,
+    PrivateSuffix,
     QuoteUnclosed,
+    ReservedLabel,
     TooManyLabels,
+    UnknownSuffix,
+    UnsupportedRule,
 }
 
 impl Kind {
-    pub(crate) const fn error_with(self, input: &str) -> Error<'_> {
-        Error { kind: self, input }
+    /// Locates this error at the given byte range
+    pub(crate) const fn at(self, start: usize, end: usize) -> Cause {
+        Cause {
+            kind: self,
+            span: Some((start, end)),
+            label: None,
+        }
     }
 }
 
@@ -101,3 +257,100 @@ impl From<std::net::AddrParseError> for Kind {
         Self::InvalidIpAddr
     }
 }
+
+/// An error kind and where it happened, relative to the input being checked
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub(crate) struct Cause {
+    kind: Kind,
+    span: Option<(usize, usize)>,
+    label: Option<usize>,
+}
+
+impl Cause {
+    pub(crate) const fn new(kind: Kind) -> Self {
+        Self {
+            kind,
+            span: None,
+            label: None,
+        }
+    }
+
+    /// Moves the span by `offset` bytes, for when the input checked was a slice
+    pub(crate) fn shift(mut self, offset: usize) -> Self {
+        if let Some((start, end)) = self.span {
+            self.span = Some((start + offset, end + offset));
+        }
+        self
+    }
+
+    /// Records the index of the label this error occurred in
+    pub(crate) const fn in_label(mut self, label: usize) -> Self {
+        self.label = Some(label);
+        self
+    }
+
+    pub(crate) const fn error_with(self, input: &str) -> Error<'_> {
+        Error {
+            kind: self.kind,
+            input,
+            span: self.span,
+            label: self.label,
+        }
+    }
+}
+
+/// Receives the errors found while checking an input
+pub(crate) trait Sink {
+    /// Returning an `Err` stops the checks
+    fn report(&mut self, cause: Cause) -> Result<()>;
+
+    /// Reports an error that prevents any further checks
+    fn fail<T>(&mut self, cause: Cause) -> Result<T> {
+        self.report(cause)?;
+        Err(cause)
+    }
+}
+
+/// Stops at the first error
+pub(crate) struct FailFast;
+
+impl Sink for FailFast {
+    #[inline]
+    fn report(&mut self, cause: Cause) -> Result<()> {
+        Err(cause)
+    }
+}
+
+/// Collects all errors
+#[cfg(feature = "alloc")]
+impl Sink for alloc::vec::Vec<Cause> {
+    fn report(&mut self, cause: Cause) -> Result<()> {
+        self.push(cause);
+        Ok(())
+    }
+}
+
+/// Adjusts errors, e.g. their positions, before passing them on
+pub(crate) struct Adjust<'a, S: ?Sized, F> {
+    sink: &'a mut S,
+    f: F,
+}
+
+impl<'a, S: Sink + ?Sized, F: Fn(Cause) -> Cause> Adjust<'a, S, F> {
+    pub(crate) fn new(sink: &'a mut S, f: F) -> Self {
+        Self { sink, f }
+    }
+}
+
+impl<S: Sink + ?Sized, F: Fn(Cause) -> Cause> Sink for Adjust<'_, S, F> {
+    #[inline]
+    fn report(&mut self, cause: Cause) -> Result<()> {
+        self.sink.report((self.f)(cause))
+    }
+}
+
+impl From<Kind> for Cause {
+    fn from(kind: Kind) -> Self {
+        Self::new(kind)
+    }
+}
@@ -0,0 +1,172 @@
+//! Hostname types
+
+use crate::error::{FailFast, Kind, Result, Sink};
+use crate::matcher;
+use crate::policy::Policy;
+use core::{fmt, str};
+use psl_types::{List, Suffix, Type};
+
+// strict LDH labels, no PSL requirement
+const POLICY: Policy = Policy::new().ascii_only(true).ipv4_numbers(true);
+
+/// Holds information about a particular hostname
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Name<'a> {
+    full: &'a str,
+    suffix: Option<Suffix<'a>>,
+}
+
+impl<'a> Name<'a> {
+    pub(crate) fn parse<T: List + ?Sized>(list: &T, name: &'a str) -> Result<Name<'a>> {
+        Self::check(list, name, &mut FailFast)
+    }
+
+    // https://tools.ietf.org/html/rfc952
+    // https://tools.ietf.org/html/rfc1123#section-2
+    pub(crate) fn check<T, S>(list: &T, name: &'a str, sink: &mut S) -> Result<Name<'a>>
+    where
+        T: List + ?Sized,
+        S: Sink + ?Sized,
+    {
+        if name.is_empty() {
+            return sink.fail(Kind::EmptyName.at(0, 0));
+        }
+        let stripped = if name.ends_with('.') {
+            name.get(..name.len() - 1).unwrap_or_default()
+        } else {
+            name
+        };
+        // the TLD can't be a number, so a hostname is
+        // never mistaken for an IPv4 address
+        matcher::check_domain_name(stripped, &POLICY, sink)?;
+        Ok(Self {
+            full: name,
+            suffix: list.suffix(name.as_bytes()),
+        })
+    }
+
+    /// Full hostname as a `str`
+    pub const fn as_str(&self) -> &'a str {
+        self.full
+    }
+
+    fn without_suffix(&self) -> Option<&'a str> {
+        let domain_len = self.full.len();
+        let suffix_len = self.suffix()?.len();
+        if domain_len == suffix_len {
+            return None;
+        }
+        self.full.get(..domain_len - suffix_len - 1)
+    }
+
+    /// The root domain (the registrable part)
+    pub fn root(&self) -> Option<&'a str> {
+        let offset = self
+            .without_suffix()?
+            .rfind('.')
+            .map(|x| x + 1)
+            .unwrap_or_default();
+        self.full.get(offset..)
+    }
+
+    /// The part before the root domain (aka. subdomain)
+    pub fn prefix(&self) -> Option<&'a str> {
+        let domain_len = self.full.len();
+        let root_len = self.root()?.len();
+        if domain_len == root_len {
+            return None;
+        }
+        self.full.get(..domain_len - root_len - 1)
+    }
+
+    /// The hostname suffix (extension)
+    pub fn suffix(&self) -> Option<&'a str> {
+        let bytes = self.suffix.as_ref()?.as_bytes();
+        str::from_utf8(bytes).ok()
+    }
+
+    /// Whether the suffix of the hostname is in the Public Suffix List
+    pub fn has_known_suffix(&self) -> bool {
+        if let Some(suffix) = self.suffix {
+            suffix.is_known()
+        } else {
+            false
+        }
+    }
+
+    /// Whether this an ICANN delegated suffix
+    ///
+    /// ICANN domains are those delegated by ICANN or part of the IANA root
+    /// zone database
+    pub fn is_icann(&self) -> bool {
+        if let Some(suffix) = self.suffix {
+            suffix.typ() == Some(Type::Icann)
+        } else {
+            false
+        }
+    }
+
+    /// Whether this is a private party delegated suffix
+    ///
+    /// PRIVATE domains are amendments submitted by the domain holder, as an
+    /// expression of how they operate their domain security policy
+    pub fn is_private(&self) -> bool {
+        if let Some(suffix) = self.suffix {
+            suffix.typ() == Some(Type::Private)
+        } else {
+            false
+        }
+    }
+}
+
+impl fmt::Display for Name<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", self.full)
+    }
+}
+
+impl PartialEq<&str> for Name<'_> {
+    fn eq(&self, other: &&str) -> bool {
+        self.full == *other
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Name;
+    use psl::List;
+
+    #[test]
+    fn parse() {
+        // Valid hostnames
+        Name::parse(&List, "localhost").unwrap();
+        Name::parse(&List, "node-1.cluster.local.").unwrap();
+        Name::parse(&List, "3com.com").unwrap();
+        Name::parse(&List, "10.0.0.example").unwrap();
+        Name::parse(&List, "ab--cd.example.com").unwrap();
+        Name::parse(&List, "foo.nan").unwrap();
+        Name::parse(&List, "inf").unwrap();
+        Name::parse(&List, "1e5").unwrap();
+
+        // Invalid hostnames
+        Name::parse(&List, "").unwrap_err();
+        Name::parse(&List, "10.0.0.1").unwrap_err();
+        Name::parse(&List, "1234").unwrap_err();
+        Name::parse(&List, "0x7f000001").unwrap_err();
+        Name::parse(&List, "www.0177").unwrap_err();
+        Name::parse(&List, "_srv.example.com").unwrap_err();
+        Name::parse(&List, "-node.example.com").unwrap_err();
+        Name::parse(&List, "node-.example.com").unwrap_err();
+        Name::parse(&List, "nöde.example.com").unwrap_err();
+        Name::parse(&List, "node..example.com").unwrap_err();
+    }
+
+    #[test]
+    fn root() {
+        let name = Name::parse(&List, "node-1.example.co.uk").unwrap();
+        assert_eq!(name.root(), Some("example.co.uk"));
+        assert_eq!(name.suffix(), Some("co.uk"));
+        assert_eq!(name.prefix(), Some("node-1"));
+        assert!(name.has_known_suffix());
+    }
+}
@@ -0,0 +1,136 @@
+//! Kubernetes object name validation
+//!
+//! These are the "DNS-1123 label", "DNS-1123 subdomain" and "DNS-1035 label"
+//! rules Kubernetes applies to object names. They are stricter than
+//! RFC 1123; only lowercase letters are allowed.
+
+use crate::error::{Adjust, FailFast, Kind, Result, Sink};
+
+const MAX_LABEL_LEN: usize = 63;
+const MAX_SUBDOMAIN_LEN: usize = 253;
+
+/// Validates a DNS-1123 label, e.g. a namespace or service name
+///
+/// At most 63 lowercase alphanumeric characters or hyphens, starting and
+/// ending with an alphanumeric character.
+pub fn validate_dns1123_label(input: &str) -> crate::Result<'_, ()> {
+    check_name(input, false, &mut FailFast).map_err(|cause| cause.error_with(input))
+}
+
+/// Validates a DNS-1123 subdomain, e.g. a pod or config map name
+///
+/// At most 253 characters of DNS-1123 labels separated by dots. Only the
+/// total length is limited, so labels can be longer than 63 characters.
+pub fn validate_dns1123_subdomain(input: &str) -> crate::Result<'_, ()> {
+    check_subdomain(input, &mut FailFast).map_err(|cause| cause.error_with(input))
+}
+
+/// Validates a DNS-1035 label
+///
+/// Like a DNS-1123 label but it must start with a letter.
+pub fn validate_dns1035_label(input: &str) -> crate::Result<'_, ()> {
+    check_name(input, true, &mut FailFast).map_err(|cause| cause.error_with(input))
+}
+
+fn check_name<S: Sink + ?Sized>(name: &str, start_alpha: bool, sink: &mut S) -> Result<()> {
+    if name.is_empty() {
+        return sink.report(Kind::EmptyName.at(0, 0));
+    }
+    if name.len() > MAX_LABEL_LEN {
+        sink.report(Kind::LabelTooLong.at(0, name.len()))?;
+    }
+    check_label(name, start_alpha, sink)
+}
+
+fn check_subdomain<S: Sink + ?Sized>(name: &str, sink: &mut S) -> Result<()> {
+    if name.is_empty() {
+        return sink.report(Kind::EmptyName.at(0, 0));
+    }
+
+    if name.len() > MAX_SUBDOMAIN_LEN {
+        sink.report(Kind::NameTooLong.at(0, name.len()))?;
+    }
+
+    let mut offset = 0;
+    for (i, label) in name.split('.').enumerate() {
+        let mut sink = Adjust::new(sink, |cause| cause.shift(offset).in_label(i));
+        check_label(label, false, &mut sink)?;
+        offset += label.len() + 1;
+    }
+
+    Ok(())
+}
+
+fn check_label<S: Sink + ?Sized>(label: &str, start_alpha: bool, sink: &mut S) -> Result<()> {
+    let (first, last) = match (label.chars().next(), label.chars().next_back()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return sink.report(Kind::EmptyLabel.at(0, 0)),
+    };
+
+    if start_alpha && !first.is_ascii_alphabetic() {
+        sink.report(Kind::LabelStartNotAlpha.at(0, first.len_utf8()))?;
+    } else if !first.is_ascii_alphanumeric() {
+        sink.report(Kind::LabelStartNotAlnum.at(0, first.len_utf8()))?;
+    }
+
+    if !last.is_ascii_alphanumeric() {
+        let start = label.len() - last.len_utf8();
+        sink.report(Kind::LabelEndNotAlnum.at(start, label.len()))?;
+    }
+
+    for (start, c) in label.char_indices() {
+        if !(c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-') {
+            sink.report(Kind::IllegalCharacter.at(start, start + c.len_utf8()))?;
+        }
+    }
+
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn dns1123_label() {
+        validate_dns1123_label("my-app").unwrap();
+        validate_dns1123_label("0day").unwrap();
+        validate_dns1123_label(&"a".repeat(63)).unwrap();
+
+        let error = validate_dns1123_label("My-app").unwrap_err();
+        assert_eq!(error.kind(), Kind::IllegalCharacter);
+        assert_eq!(error.span(), Some(0..1));
+        let error = validate_dns1123_label("my-app-").unwrap_err();
+        assert_eq!(error.kind(), Kind::LabelEndNotAlnum);
+        let long = "a".repeat(64);
+        let error = validate_dns1123_label(&long).unwrap_err();
+        assert_eq!(error.kind(), Kind::LabelTooLong);
+        validate_dns1123_label("my.app").unwrap_err();
+        validate_dns1123_label("").unwrap_err();
+    }
+
+    #[test]
+    fn dns1123_subdomain() {
+        validate_dns1123_subdomain("my-app.example.com").unwrap();
+        validate_dns1123_subdomain("0.1").unwrap();
+        let long = format!("{}.example.com", "a".repeat(64));
+        validate_dns1123_subdomain(&long).unwrap();
+
+        let error = validate_dns1123_subdomain("my-app..example").unwrap_err();
+        assert_eq!(error.kind(), Kind::EmptyLabel);
+        assert_eq!(error.label(), Some(1));
+        let error = validate_dns1123_subdomain("my-app.Example").unwrap_err();
+        assert_eq!(error.span(), Some(7..8));
+        validate_dns1123_subdomain("example.com.").unwrap_err();
+        validate_dns1123_subdomain(&"a.".repeat(127)).unwrap_err();
+    }
+
+    #[test]
+    fn dns1035_label() {
+        validate_dns1035_label("my-app").unwrap();
+
+        let error = validate_dns1035_label("0day").unwrap_err();
+        assert_eq!(error.kind(), Kind::LabelStartNotAlpha);
+        validate_dns1035_label("-app").unwrap_err();
+    }
+}
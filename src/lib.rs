@@ -44,47 +44,116 @@
 #![cfg_attr(not(feature = "std"), no_std)]
 #![forbid(unsafe_code)]
 
+#[cfg(feature = "alloc")]
+extern crate alloc;
+
+#[cfg(feature = "alloc")]
+pub mod blocklist;
+#[cfg(feature = "std")]
+pub mod build;
+pub mod dmarc;
 pub mod dns;
 pub mod domain;
 pub mod email;
 pub mod error;
+pub mod hostname;
+pub mod kubernetes;
+pub mod list;
+#[cfg(feature = "alloc")]
+pub mod mailto;
+#[cfg(feature = "alloc")]
+pub mod map;
 mod matcher;
 #[cfg(feature = "net")]
 pub mod net;
 pub mod parser;
+pub mod policy;
+mod punycode;
+#[cfg(feature = "security")]
+pub mod security;
 #[cfg(feature = "serde")]
 mod serde;
+pub mod typosquat;
 
 #[cfg(not(any(feature = "psl", feature = "publicsuffix")))]
-pub use crate::empty_psl::{parse_dns_name, parse_domain_name, parse_email_address};
+pub use crate::empty_psl::{
+    parse_dns_name, parse_domain_name, parse_email_address, parse_hostname,
+};
 #[cfg(feature = "psl")]
-pub use crate::psl::{parse_dns_name, parse_domain_name, parse_email_address};
+pub use crate::psl::{
+    parse_dns_name, parse_dns_name_icann, parse_dns_name_private, parse_domain_name,
+    parse_domain_name_icann, parse_domain_name_private, parse_email_address, parse_hostname,
+};
 
 /// The static implementation of the public suffix list
+///
+/// Its rules can't be enumerated, as the `psl` crate only compiles them
+/// into lookup code; see the `list` module. It doesn't record the version
+/// or commit of the list it was generated from either, so its `Versioned`
+/// metadata is the version of the `psl` crate, which is pinned to
+/// identify the data.
 #[cfg(feature = "psl")]
 pub mod psl {
-    use crate::parser::{DnsName, DomainName, EmailAddress};
-    use crate::{dns, domain, email, Result};
+    use crate::list::{Metadata, Section, Versioned};
+    use crate::parser::{DnsName, DomainName, EmailAddress, Hostname};
+    use crate::{dns, domain, email, hostname, Result};
+    use psl_types::Type;
 
     pub use psl::List;
 
-    pub fn parse_domain_name(input: &str) -> Result<domain::Name> {
+    /// Only the ICANN section of the list
+    pub const ICANN: Section<List> = Section::new(List, Type::Icann);
+
+    /// Only the PRIVATE section of the list
+    pub const PRIVATE: Section<List> = Section::new(List, Type::Private);
+
+    impl Versioned for List {
+        fn metadata(&self) -> Metadata<'_> {
+            Metadata::new().with_package("psl 2.1.241")
+        }
+    }
+
+    pub fn parse_domain_name(input: &str) -> Result<'_, domain::Name<'_>> {
         List.parse_domain_name(input)
     }
 
-    pub fn parse_dns_name(input: &str) -> Result<dns::Name> {
+    /// Parses a domain name, ignoring private suffixes like `github.io`
+    pub fn parse_domain_name_icann(input: &str) -> Result<'_, domain::Name<'_>> {
+        ICANN.parse_domain_name(input)
+    }
+
+    /// Parses a domain name, ignoring ICANN suffixes like `co.uk`
+    pub fn parse_domain_name_private(input: &str) -> Result<'_, domain::Name<'_>> {
+        PRIVATE.parse_domain_name(input)
+    }
+
+    pub fn parse_dns_name(input: &str) -> Result<'_, dns::Name<'_>> {
         List.parse_dns_name(input)
     }
 
-    pub fn parse_email_address(input: &str) -> Result<email::Address> {
+    /// Parses a DNS name, ignoring private suffixes like `github.io`
+    pub fn parse_dns_name_icann(input: &str) -> Result<'_, dns::Name<'_>> {
+        ICANN.parse_dns_name(input)
+    }
+
+    /// Parses a DNS name, ignoring ICANN suffixes like `co.uk`
+    pub fn parse_dns_name_private(input: &str) -> Result<'_, dns::Name<'_>> {
+        PRIVATE.parse_dns_name(input)
+    }
+
+    pub fn parse_email_address(input: &str) -> Result<'_, email::Address<'_>> {
         List.parse_email_address(input)
     }
+
+    pub fn parse_hostname(input: &str) -> Result<'_, hostname::Name<'_>> {
+        List.parse_hostname(input)
+    }
 }
 
 #[cfg(not(any(feature = "psl", feature = "publicsuffix")))]
 mod empty_psl {
-    use crate::parser::{DnsName, DomainName, EmailAddress};
-    use crate::{dns, domain, email, Result};
+    use crate::parser::{DnsName, DomainName, EmailAddress, Hostname};
+    use crate::{dns, domain, email, hostname, Result};
     use psl_types::Info;
 
     pub struct List;
@@ -104,23 +173,93 @@ mod empty_psl {
         }
     }
 
-    pub fn parse_domain_name(input: &str) -> Result<domain::Name> {
+    pub fn parse_domain_name(input: &str) -> Result<'_, domain::Name<'_>> {
         List.parse_domain_name(input)
     }
 
-    pub fn parse_dns_name(input: &str) -> Result<dns::Name> {
+    pub fn parse_dns_name(input: &str) -> Result<'_, dns::Name<'_>> {
         List.parse_dns_name(input)
     }
 
-    pub fn parse_email_address(input: &str) -> Result<email::Address> {
+    pub fn parse_email_address(input: &str) -> Result<'_, email::Address<'_>> {
         List.parse_email_address(input)
     }
+
+    pub fn parse_hostname(input: &str) -> Result<'_, hostname::Name<'_>> {
+        List.parse_hostname(input)
+    }
 }
 
 /// The dynamic implementation of the public suffix list
+///
+/// `publicsuffix::List` does not keep the header of the list it was
+/// parsed from, and can't enumerate its rules. Parse it as `Parsed` to
+/// keep its input, which has both.
 #[cfg(feature = "publicsuffix")]
 pub mod publicsuffix {
-    pub use publicsuffix::{IcannList, List, PrivateList};
+    #[cfg(feature = "alloc")]
+    use crate::list::{parse_rules, Rules};
+    use crate::list::{Metadata, Versioned};
+    use psl_types::Info;
+
+    pub use publicsuffix::{Error, IcannList, List, PrivateList};
+
+    /// A `List` along with the input it was parsed from
+    ///
+    /// ```rust
+    /// use addr::list::Versioned;
+    /// use addr::publicsuffix::Parsed;
+    ///
+    /// let list = Parsed::parse(
+    ///     "// VERSION: 2024-06-25_10-05-23_UTC
+    ///     // ===BEGIN ICANN DOMAINS===
+    ///     com",
+    /// )
+    /// .unwrap();
+    /// assert_eq!(list.metadata().version(), Some("2024-06-25_10-05-23_UTC"));
+    /// assert_eq!(list.to_rules().unwrap().len(), 1);
+    /// ```
+    #[derive(Clone, Eq, PartialEq, Debug)]
+    pub struct Parsed<'a> {
+        list: List,
+        input: &'a str,
+    }
+
+    impl<'a> Parsed<'a> {
+        /// Parses a list in the format of `public_suffix_list.dat`
+        pub fn parse(input: &'a str) -> Result<Self, Error> {
+            Ok(Self {
+                list: input.parse()?,
+                input,
+            })
+        }
+
+        /// The list itself
+        pub const fn list(&self) -> &List {
+            &self.list
+        }
+
+        /// Parses the same input into `Rules`, so they can be listed
+        #[cfg(feature = "alloc")]
+        pub fn to_rules(&self) -> crate::Result<'a, Rules> {
+            parse_rules(self.input)
+        }
+    }
+
+    impl Versioned for Parsed<'_> {
+        fn metadata(&self) -> Metadata<'_> {
+            Metadata::parse(self.input)
+        }
+    }
+
+    impl psl_types::List for Parsed<'_> {
+        fn find<'a, T>(&self, labels: T) -> Info
+        where
+            T: Iterator<Item = &'a [u8]>,
+        {
+            self.list.find(labels)
+        }
+    }
 }
 
 /// Custom result type
@@ -0,0 +1,421 @@
+#[cfg(feature = "alloc")]
+use super::compiled::to_rules;
+use super::compiled::{walk, Trie};
+#[cfg(feature = "alloc")]
+use super::Rules;
+use super::{Entry, Metadata, Versioned};
+use crate::error::Kind;
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeMap, vec::Vec};
+#[cfg(feature = "alloc")]
+use psl_types::Type;
+use psl_types::{Info, List};
+
+const MAGIC: &[u8; 8] = b"addr-psl";
+const HEADER_LEN: usize = 28;
+const ENTRY_LEN: usize = 16;
+
+// entry flags
+const ICANN: u8 = 1;
+const PRIVATE: u8 = 2;
+const EXCEPTION_RULE: u8 = 4;
+
+/// A list in a compact binary format, read in place
+///
+/// The bytes are written by `Rules::to_bytes` and can come from anywhere,
+/// e.g. `include_bytes!` or a memory-mapped file. They are checked once
+/// when the list is created, so loading is much faster than parsing
+/// `public_suffix_list.dat`, and lookups don't allocate.
+///
+/// The format is little-endian: a 28 byte header with the magic bytes
+/// `addr-psl`, the format version, two reserved zero bytes, the number of
+/// entries, the sizes of the labels and metadata and a CRC-32 of
+/// everything after the header, followed by the entries of the trie in
+/// breadth-first order, their labels and the metadata.
+///
+/// ```rust
+/// use addr::list::{parse_rules, Binary};
+/// use addr::parser::DomainName;
+///
+/// let bytes = parse_rules("uk\nco.uk").unwrap().to_bytes().unwrap();
+/// let list = Binary::new(&bytes).unwrap();
+///
+/// let domain = list.parse_domain_name("www.example.co.uk").unwrap();
+/// assert_eq!(domain.root(), Some("example.co.uk"));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Binary<'a> {
+    entries: &'a [u8],
+    labels: &'a [u8],
+    metadata: Metadata<'a>,
+}
+
+impl<'a> Binary<'a> {
+    /// The version of the format written and read by this crate
+    pub const FORMAT: u16 = 1;
+
+    /// Checks the bytes of a list and reads it in place
+    ///
+    /// Errors are `InvalidBinaryList`, spanning the offending bytes. As
+    /// the bytes aren't text, the input of the error is empty.
+    pub fn new(bytes: &'a [u8]) -> crate::Result<'a, Self> {
+        Self::read(bytes).map_err(|cause| cause.error_with(""))
+    }
+
+    fn read(bytes: &'a [u8]) -> crate::error::Result<Self> {
+        let invalid = |start: usize, end: usize| Kind::InvalidBinaryList.at(start, end);
+        if bytes.len() < HEADER_LEN {
+            return Err(invalid(0, bytes.len()));
+        }
+        if &bytes[..8] != MAGIC {
+            return Err(invalid(0, 8));
+        }
+        if u16_at(bytes, 8) != Self::FORMAT {
+            return Err(invalid(8, 10));
+        }
+        // reserved
+        if u16_at(bytes, 10) != 0 {
+            return Err(invalid(10, 12));
+        }
+        let count = u32_at(bytes, 12) as usize;
+        let labels_len = u32_at(bytes, 16) as usize;
+        let version_len = usize::from(u16_at(bytes, 20));
+        let commit_len = usize::from(u16_at(bytes, 22));
+
+        let body = &bytes[HEADER_LEN..];
+        let len = count
+            .checked_mul(ENTRY_LEN)
+            .and_then(|len| len.checked_add(labels_len))
+            .and_then(|len| len.checked_add(version_len + commit_len));
+        if len != Some(body.len()) {
+            return Err(invalid(HEADER_LEN, bytes.len()));
+        }
+        if crc32(body) != u32_at(bytes, 24) {
+            return Err(invalid(24, HEADER_LEN));
+        }
+
+        let (entries, rest) = body.split_at(count * ENTRY_LEN);
+        let (labels, rest) = rest.split_at(labels_len);
+        let (version, commit) = rest.split_at(version_len);
+        let text = |bytes: &'a [u8], start: usize| match bytes {
+            [] => Ok(None),
+            bytes => match core::str::from_utf8(bytes) {
+                Ok(text) => Ok(Some(text)),
+                Err(_) => Err(invalid(start, start + bytes.len())),
+            },
+        };
+        let metadata_start = bytes.len() - version_len - commit_len;
+        let list = Self {
+            entries,
+            labels,
+            metadata: Metadata::from_parts(
+                text(version, metadata_start)?,
+                text(commit, metadata_start + version_len)?,
+            ),
+        };
+
+        // lookups can then rely on the entries being in bounds, and the
+        // entries must be laid out breadth first like `Rules::entries` does,
+        // so every entry but the root is the child of exactly one entry
+        // before it and walking the trie always ends
+        let span = |index: usize| {
+            invalid(
+                HEADER_LEN + index * ENTRY_LEN,
+                HEADER_LEN + (index + 1) * ENTRY_LEN,
+            )
+        };
+        let mut next = 1;
+        for index in 0..count {
+            let raw = &entries[index * ENTRY_LEN..(index + 1) * ENTRY_LEN];
+            let label_end = u32_at(raw, 0) as usize + usize::from(u16_at(raw, 12));
+            let first = u64::from(u32_at(raw, 4));
+            let children_end = first + u64::from(u32_at(raw, 8));
+            if label_end > labels_len
+                || first != next
+                || first <= index as u64
+                || children_end > count as u64
+                || raw[14] & !(ICANN | PRIVATE | EXCEPTION_RULE) != 0
+                || raw[14] & (ICANN | PRIVATE) == ICANN | PRIVATE
+                || raw[15] != 0
+            {
+                return Err(span(index));
+            }
+            next = children_end;
+        }
+        if count > 0 && next != count as u64 {
+            return Err(invalid(HEADER_LEN, HEADER_LEN + count * ENTRY_LEN));
+        }
+        // the children of an entry are searched by their labels
+        for index in 0..count {
+            let entry = list.entry(index).ok_or_else(|| span(index))?;
+            let first = entry.first() as usize;
+            for child in first + 1..first + entry.count() as usize {
+                let label = |index| list.entry(index).map(|entry| entry.label());
+                if label(child - 1) >= label(child) {
+                    return Err(span(child));
+                }
+            }
+        }
+        Ok(list)
+    }
+
+    /// The version and commit of the list it was written from
+    pub const fn metadata(&self) -> Metadata<'a> {
+        self.metadata
+    }
+
+    /// Reads the rules back out of the list, so they can be listed
+    #[cfg(feature = "alloc")]
+    pub fn to_rules(&self) -> Rules {
+        to_rules(self, self.metadata)
+    }
+}
+
+impl<'a> Trie<'a> for Binary<'a> {
+    fn entry(&self, index: usize) -> Option<Entry<'a>> {
+        let raw = self
+            .entries
+            .get(index * ENTRY_LEN..(index + 1) * ENTRY_LEN)?;
+        let offset = u32_at(raw, 0) as usize;
+        let label = self
+            .labels
+            .get(offset..offset + usize::from(u16_at(raw, 12)))?;
+        let (first, count) = (u32_at(raw, 4), u32_at(raw, 8));
+        let entry = match raw[14] & (ICANN | PRIVATE) {
+            ICANN => Entry::icann(label, first, count),
+            PRIVATE => Entry::private(label, first, count),
+            _ => Entry::branch(label, first, count),
+        };
+        if raw[14] & EXCEPTION_RULE == 0 {
+            Some(entry)
+        } else {
+            Some(entry.exception())
+        }
+    }
+}
+
+impl Versioned for Binary<'_> {
+    fn metadata(&self) -> Metadata<'_> {
+        self.metadata
+    }
+}
+
+impl List for Binary<'_> {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        walk(self, labels)
+    }
+}
+
+fn u16_at(bytes: &[u8], index: usize) -> u16 {
+    u16::from_le_bytes([bytes[index], bytes[index + 1]])
+}
+
+fn u32_at(bytes: &[u8], index: usize) -> u32 {
+    let mut buf = [0; 4];
+    buf.copy_from_slice(&bytes[index..index + 4]);
+    u32::from_le_bytes(buf)
+}
+
+// CRC-32 as used by zlib and PNG
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut table = [0u32; 256];
+    for (index, value) in table.iter_mut().enumerate() {
+        let mut crc = index as u32;
+        for _ in 0..8 {
+            let mask = (crc & 1).wrapping_neg();
+            crc = (crc >> 1) ^ (0xEDB8_8320 & mask);
+        }
+        *value = crc;
+    }
+    let mut crc = !0u32;
+    for &byte in bytes {
+        crc = (crc >> 8) ^ table[((crc ^ u32::from(byte)) & 0xff) as usize];
+    }
+    !crc
+}
+
+#[cfg(feature = "alloc")]
+impl Rules {
+    /// Writes the rules in the format read by `Binary`
+    ///
+    /// Labels, versions and commits longer than 65535 bytes can't be
+    /// written, and are `LabelTooLong` and `MetadataTooLong` errors.
+    pub fn to_bytes(&self) -> crate::Result<'_, Vec<u8>> {
+        let entries = self.entries();
+        let metadata = self.metadata();
+        let version = metadata.version().unwrap_or_default();
+        let commit = metadata.commit().unwrap_or_default();
+        for text in &[version, commit] {
+            if text.len() > usize::from(core::u16::MAX) {
+                return Err(Kind::MetadataTooLong.at(0, text.len()).error_with(text));
+            }
+        }
+
+        // labels like `com` are stored once
+        let mut labels = Vec::new();
+        let mut offsets = BTreeMap::new();
+        let mut body = Vec::with_capacity(entries.len() * ENTRY_LEN);
+        for entry in &entries {
+            if entry.label().len() > usize::from(core::u16::MAX) {
+                let label = core::str::from_utf8(entry.label()).unwrap_or_default();
+                return Err(Kind::LabelTooLong.at(0, label.len()).error_with(label));
+            }
+            let offset = *offsets.entry(entry.label()).or_insert_with(|| {
+                labels.extend_from_slice(entry.label());
+                labels.len() - entry.label().len()
+            });
+            let flags = match entry.typ() {
+                Some(Type::Icann) => ICANN,
+                Some(Type::Private) => PRIVATE,
+                None => 0,
+            } | if entry.is_exception() {
+                EXCEPTION_RULE
+            } else {
+                0
+            };
+            body.extend_from_slice(&(offset as u32).to_le_bytes());
+            body.extend_from_slice(&entry.first().to_le_bytes());
+            body.extend_from_slice(&entry.count().to_le_bytes());
+            body.extend_from_slice(&(entry.label().len() as u16).to_le_bytes());
+            body.extend_from_slice(&[flags, 0]);
+        }
+        body.extend_from_slice(&labels);
+        body.extend_from_slice(version.as_bytes());
+        body.extend_from_slice(commit.as_bytes());
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(MAGIC);
+        bytes.extend_from_slice(&Binary::FORMAT.to_le_bytes());
+        bytes.extend_from_slice(&[0, 0]);
+        bytes.extend_from_slice(&(entries.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(labels.len() as u32).to_le_bytes());
+        bytes.extend_from_slice(&(version.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&(commit.len() as u16).to_le_bytes());
+        bytes.extend_from_slice(&crc32(&body).to_le_bytes());
+        bytes.extend_from_slice(&body);
+        Ok(bytes)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::error::Kind;
+    use crate::list::{is_suffix, parse_rules};
+    use crate::parser::DomainName;
+
+    #[test]
+    fn binary() {
+        let dat = include_str!("../../tests/public_suffix_list.dat");
+        let rules = parse_rules(dat).unwrap();
+        let bytes = rules.to_bytes().unwrap();
+        let list = Binary::new(&bytes).unwrap();
+        for rule in rules.iter() {
+            let name = rule.name().replace('*', "wildcard");
+            let name = format!("www.example.{}", name);
+            assert_eq!(
+                list.find(name.rsplit('.').map(str::as_bytes)),
+                rules.find(name.rsplit('.').map(str::as_bytes)),
+                "{}",
+                name
+            );
+        }
+        assert_eq!(list.metadata(), rules.metadata());
+        let name = list.parse_domain_name("www.example.xn--fiqs8s").unwrap();
+        assert_eq!(name.root(), Some("example.xn--fiqs8s"));
+        assert!(is_suffix(&list, "xn--55qx5d.xn--j6w193g"));
+        let read = list.to_rules();
+        assert_eq!(read.len(), rules.len());
+        assert!(rules
+            .iter()
+            .all(|rule| read.get(rule.as_str()) == Some(rule)));
+
+        let input = "// VERSION: 2024-06-25_10-05-23_UTC\nuk\nco.uk";
+        let bytes = parse_rules(input).unwrap().to_bytes().unwrap();
+        let list = Binary::new(&bytes).unwrap();
+        assert_eq!(list.metadata(), Metadata::parse(input));
+        assert!(is_suffix(&list, "co.uk"));
+        assert!(!is_suffix(&list, "example.co.uk"));
+        let empty = Rules::new().to_bytes().unwrap();
+        assert!(!is_suffix(&Binary::new(&empty).unwrap(), "co.uk"));
+    }
+
+    #[test]
+    fn invalid_binary() {
+        let bytes = parse_rules("uk\nco.uk").unwrap().to_bytes().unwrap();
+        let span = |bytes: &[u8]| {
+            let error = Binary::new(bytes).unwrap_err();
+            assert_eq!(error.kind(), Kind::InvalidBinaryList);
+            error.span().unwrap()
+        };
+
+        assert_eq!(span(&bytes[..20]), 0..20);
+        assert_eq!(span(&bytes[..bytes.len() - 1]), 28..bytes.len() - 1);
+        let mut bad = bytes.clone();
+        bad[0] = b'x';
+        assert_eq!(span(&bad), 0..8);
+        let mut bad = bytes.clone();
+        bad[8] = 2;
+        assert_eq!(span(&bad), 8..10);
+        let mut bad = bytes.clone();
+        bad[10] = 1;
+        assert_eq!(span(&bad), 10..12);
+        let mut bad = bytes.clone();
+        *bad.last_mut().unwrap() ^= 1;
+        assert_eq!(span(&bad), 24..28);
+
+        let error = Binary::new(&bytes[..8]).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "not a valid binary suffix list (bytes 0..8)"
+        );
+        assert_eq!(format!("{:#}", error), error.to_string());
+
+        // entries with a valid checksum
+        let checksummed = |mut bytes: Vec<u8>| {
+            let checksum = crc32(&bytes[28..]).to_le_bytes();
+            bytes[24..28].copy_from_slice(&checksum);
+            bytes
+        };
+        let mut bad = bytes.clone();
+        bad[28 + 8] = 100;
+        assert_eq!(span(&checksummed(bad)), 28..44);
+        // `co` pointing back at `uk` would make a cycle
+        let mut bad = bytes;
+        bad[60 + 4] = 1;
+        bad[60 + 8] = 1;
+        assert_eq!(span(&checksummed(bad)), 60..76);
+        // children out of order
+        let bytes = parse_rules("a.uk\nb.uk").unwrap().to_bytes().unwrap();
+        let mut bad = bytes.clone();
+        bad[60..76].copy_from_slice(&bytes[76..92]);
+        bad[76..92].copy_from_slice(&bytes[60..76]);
+        assert_eq!(span(&checksummed(bad)), 76..92);
+
+        assert_eq!(crc32(b"123456789"), 0xCBF4_3926);
+    }
+
+    #[test]
+    fn too_long() {
+        let version = "1".repeat(usize::from(core::u16::MAX) + 1);
+        let rules = parse_rules(&format!("// VERSION: {}\nuk", version)).unwrap();
+        let error = rules.to_bytes().unwrap_err();
+        assert_eq!(error.kind(), Kind::MetadataTooLong);
+        assert_eq!(error.input(), version);
+
+        let label = "a".repeat(usize::from(core::u16::MAX) + 1);
+        let rules = parse_rules(&format!("{}.uk", label)).unwrap();
+        assert_eq!(rules.to_bytes().unwrap_err().kind(), Kind::LabelTooLong);
+
+        let label = "a".repeat(usize::from(core::u16::MAX));
+        let rules = parse_rules(&format!("{}.uk", label)).unwrap();
+        let bytes = rules.to_bytes().unwrap();
+        assert!(is_suffix(
+            &Binary::new(&bytes).unwrap(),
+            &format!("{}.uk", label)
+        ));
+    }
+}
@@ -0,0 +1,211 @@
+use super::{Rule, Rules};
+use crate::domain;
+use alloc::vec::Vec;
+use psl_types::List;
+
+/// The rules added, removed and moved between two versions of a list
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Changes {
+    added: Vec<Rule>,
+    removed: Vec<Rule>,
+    moved: Vec<Rule>,
+}
+
+impl Changes {
+    /// Compares the rules of two lists
+    pub fn between(old: &Rules, new: &Rules) -> Self {
+        let mut changes = Self::default();
+        for rule in new.iter() {
+            match old.get(rule.as_str()) {
+                Some(found) if found.typ() == rule.typ() => {}
+                Some(_) => changes.moved.push(rule.clone()),
+                None => changes.added.push(rule.clone()),
+            }
+        }
+        changes.removed = old
+            .iter()
+            .filter(|rule| new.get(rule.as_str()).is_none())
+            .cloned()
+            .collect();
+        changes
+    }
+
+    /// Rules only in the new list
+    pub fn added(&self) -> &[Rule] {
+        &self.added
+    }
+
+    /// Rules only in the old list
+    pub fn removed(&self) -> &[Rule] {
+        &self.removed
+    }
+
+    /// Rules that are in the other section of the new list
+    ///
+    /// These are the rules of the new list, so their type is the new one.
+    pub fn moved(&self) -> &[Rule] {
+        &self.moved
+    }
+
+    /// Whether both lists have the same rules
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.moved.is_empty()
+    }
+}
+
+/// A domain name that is split differently by a new list
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Affected<'a> {
+    before: domain::Name<'a>,
+    after: Option<domain::Name<'a>>,
+}
+
+impl<'a> Affected<'a> {
+    /// The name as parsed with the old list
+    pub const fn before(&self) -> domain::Name<'a> {
+        self.before
+    }
+
+    /// The name as parsed with the new list
+    ///
+    /// This is `None` if the new list can't find a suffix for it at all.
+    pub const fn after(&self) -> Option<domain::Name<'a>> {
+        self.after
+    }
+
+    /// Whether the registrable part of the name changed
+    pub fn root_changed(&self) -> bool {
+        self.after.map(|after| after.root()) != Some(self.before.root())
+    }
+
+    /// Whether the suffix or its section changed
+    pub fn suffix_changed(&self) -> bool {
+        match self.after {
+            Some(after) => {
+                after.suffix() != self.before.suffix()
+                    || after.is_icann() != self.before.is_icann()
+                    || after.has_known_suffix() != self.before.has_known_suffix()
+            }
+            None => true,
+        }
+    }
+}
+
+/// The names that `new` splits differently from the list they were
+/// parsed with
+///
+/// A name is affected if its root, its suffix or the section of its
+/// suffix changes. Names are reported in the order they are given.
+///
+/// ```rust
+/// use addr::list::{affected, parse_rules};
+/// use addr::parser::DomainName;
+///
+/// let old = parse_rules("com\nio").unwrap();
+/// let new = parse_rules("com\nio\n// ===BEGIN PRIVATE DOMAINS===\ngithub.io").unwrap();
+/// let names = ["example.com", "example.github.io"];
+/// let names = names.iter().map(|name| old.parse_domain_name(name).unwrap());
+///
+/// let changed = affected(&new, names);
+/// assert_eq!(changed.len(), 1);
+/// assert_eq!(changed[0].before().root(), Some("github.io"));
+/// assert_eq!(changed[0].after().unwrap().root(), Some("example.github.io"));
+/// ```
+pub fn affected<'a, L, I>(new: &L, names: I) -> Vec<Affected<'a>>
+where
+    L: List + ?Sized,
+    I: IntoIterator<Item = domain::Name<'a>>,
+{
+    names
+        .into_iter()
+        .map(|before| Affected {
+            before,
+            after: domain::Name::parse(new, before.as_str()).ok(),
+        })
+        .filter(|name| name.root_changed() || name.suffix_changed())
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{parse_rules, LIST};
+    use crate::parser::DomainName;
+    use psl_types::Type;
+
+    #[test]
+    fn changes() {
+        let old = parse_rules(LIST).unwrap();
+        let new = parse_rules(
+            "com
+            uk
+            *.ck
+            !www.ck
+            github.io
+            blogspot.co.uk
+            *.compute.example",
+        )
+        .unwrap();
+        let changes = Changes::between(&old, &new);
+        let added = changes.added().iter().map(Rule::as_str).collect::<Vec<_>>();
+        let removed = changes
+            .removed()
+            .iter()
+            .map(Rule::as_str)
+            .collect::<Vec<_>>();
+        let moved = changes.moved().iter().map(Rule::as_str).collect::<Vec<_>>();
+        assert_eq!(added, ["*.compute.example"]);
+        assert_eq!(removed, ["co.uk"]);
+        // the private rules moved to the ICANN section
+        assert_eq!(moved, ["github.io", "blogspot.co.uk"]);
+        assert_eq!(changes.moved()[0].typ(), Type::Icann);
+        assert!(Changes::between(&old, &old).is_empty());
+    }
+
+    #[test]
+    fn affected_names() {
+        let old = parse_rules(LIST).unwrap();
+        let new = parse_rules(
+            "// ===BEGIN ICANN DOMAINS===
+            com
+            uk
+            co.uk
+            *.ck
+            // ===BEGIN PRIVATE DOMAINS===
+            github.io
+            example.com",
+        )
+        .unwrap();
+        let names = [
+            "www.example.com",
+            "www.example.co.uk",
+            "www.ck",
+            "blog.blogspot.co.uk",
+            "user.github.io",
+        ];
+        let names = names
+            .iter()
+            .map(|name| old.parse_domain_name(name).unwrap());
+        let changed = affected(&new, names);
+        let found = changed
+            .iter()
+            .map(|name| name.before().as_str())
+            .collect::<Vec<_>>();
+        assert_eq!(found, ["www.example.com", "www.ck", "blog.blogspot.co.uk"]);
+
+        // example.com is now a private suffix
+        let name = changed[0];
+        assert_eq!(name.before().root(), Some("example.com"));
+        assert_eq!(name.after().unwrap().root(), Some("www.example.com"));
+        assert!(name.root_changed() && name.suffix_changed());
+        assert!(name.after().unwrap().is_private());
+
+        // the exception is gone, so www.ck is a suffix
+        assert_eq!(changed[1].after().unwrap().root(), None);
+
+        // blogspot.co.uk was removed, the root changes with the suffix
+        let name = changed[2];
+        assert_eq!(name.before().suffix(), "blogspot.co.uk");
+        assert_eq!(name.after().unwrap().root(), Some("blogspot.co.uk"));
+    }
+}
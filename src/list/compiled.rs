@@ -0,0 +1,365 @@
+use super::{Metadata, Versioned, WILDCARD};
+#[cfg(feature = "alloc")]
+use super::{Rules, MAX_LABELS};
+#[cfg(feature = "alloc")]
+use alloc::{collections::VecDeque, string::String, vec, vec::Vec};
+use core::cmp::Ordering;
+use psl_types::{Info, List, Type};
+
+/// A list compiled into static tables
+///
+/// Lookups don't allocate, so this works without `alloc`. Lists are
+/// usually generated by `build::compile_file` in a build script rather
+/// than written by hand, and then included where they are used:
+///
+/// ```rust,ignore
+/// use addr::parser::DomainName;
+///
+/// mod psl {
+///     include!(concat!(env!("OUT_DIR"), "/psl.rs"));
+/// }
+///
+/// fn root(name: &str) -> Option<&str> {
+///     psl::LIST.parse_domain_name(name).ok()?.root()
+/// }
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Compiled<'a> {
+    entries: &'a [Entry<'a>],
+    metadata: Metadata<'a>,
+}
+
+impl<'a> Compiled<'a> {
+    /// Creates a list from its entries, the first of which is the root
+    pub const fn new(entries: &'a [Entry<'a>], metadata: Metadata<'a>) -> Self {
+        Self { entries, metadata }
+    }
+
+    /// The version and commit of the list it was compiled from
+    pub const fn metadata(&self) -> Metadata<'a> {
+        self.metadata
+    }
+
+    /// Reads the rules back out of the list, so they can be listed
+    #[cfg(feature = "alloc")]
+    pub fn to_rules(&self) -> Rules {
+        to_rules(self, self.metadata)
+    }
+}
+
+impl<'a> Trie<'a> for Compiled<'a> {
+    fn entry(&self, index: usize) -> Option<Entry<'a>> {
+        self.entries.get(index).copied()
+    }
+}
+
+impl Versioned for Compiled<'_> {
+    fn metadata(&self) -> Metadata<'_> {
+        self.metadata
+    }
+}
+
+impl List for Compiled<'_> {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        walk(self, labels)
+    }
+}
+
+// the entries of a compiled list, however they are stored
+pub(super) trait Trie<'a> {
+    fn entry(&self, index: usize) -> Option<Entry<'a>>;
+
+    fn root(&self) -> Option<Entry<'a>> {
+        self.entry(0)
+    }
+
+    fn child(&self, entry: &Entry<'a>, label: &[u8]) -> Option<Entry<'a>> {
+        // a binary search over the children
+        let mut low = entry.first as usize;
+        let mut high = low + entry.count as usize;
+        while low < high {
+            let middle = low + (high - low) / 2;
+            let child = self.entry(middle)?;
+            match child.label.cmp(label) {
+                Ordering::Less => low = middle + 1,
+                Ordering::Greater => high = middle,
+                Ordering::Equal => return Some(child),
+            }
+        }
+        None
+    }
+}
+
+// rebuilds the text of every rule in the trie
+#[cfg(feature = "alloc")]
+pub(super) fn to_rules<'a, L>(trie: &L, metadata: Metadata<'_>) -> Rules
+where
+    L: Trie<'a> + ?Sized,
+{
+    let mut rules = Rules::new();
+    rules.version = metadata.version().map(Into::into);
+    rules.commit = metadata.commit().map(Into::into);
+    // names have at most `MAX_LABELS` labels, which also stops at a cycle
+    // in entries that weren't written by this crate
+    let mut stack = match trie.root() {
+        Some(root) => vec![(root, String::new(), 0)],
+        None => Vec::new(),
+    };
+    let mut found = Vec::new();
+    while let Some((entry, name, depth)) = stack.pop() {
+        if let (Some(typ), false) = (entry.typ, name.is_empty()) {
+            let mut rule = String::new();
+            if entry.exception {
+                rule.push('!');
+            }
+            rule.push_str(&name);
+            found.push((rule, typ));
+        }
+        if depth == MAX_LABELS {
+            continue;
+        }
+        let first = entry.first as usize;
+        for index in (first..first + entry.count as usize).rev() {
+            let child = match trie.entry(index) {
+                Some(child) => child,
+                None => continue,
+            };
+            let label = match core::str::from_utf8(child.label) {
+                Ok(label) => label,
+                Err(_) => continue,
+            };
+            let mut child_name = String::from(label);
+            if !name.is_empty() {
+                child_name.push('.');
+                child_name.push_str(&name);
+            }
+            stack.push((child, child_name, depth + 1));
+        }
+    }
+    let (ascii, other): (Vec<_>, Vec<_>) = found.into_iter().partition(|(rule, _)| rule.is_ascii());
+    for (rule, typ) in other {
+        // the entries were checked when the list was written
+        let _ = rules.insert(&rule, typ);
+    }
+    // skips the A-label forms of the other rules, which `insert` added
+    for (rule, typ) in ascii {
+        if !rules.ends_at(rule.trim_start_matches('!')) {
+            let _ = rules.insert(&rule, typ);
+        }
+    }
+    rules
+}
+
+pub(super) fn walk<'a, 'b, L, T>(trie: &L, mut labels: T) -> Info
+where
+    L: Trie<'a> + ?Sized,
+    T: Iterator<Item = &'b [u8]>,
+{
+    // the implicit `*` rule makes the last label a suffix
+    let (mut entry, mut info) = match labels.next() {
+        Some(label) => {
+            let info = Info {
+                len: label.len(),
+                typ: None,
+            };
+            match trie.root().and_then(|root| trie.child(&root, label)) {
+                Some(entry) => (
+                    entry,
+                    Info {
+                        typ: entry.typ,
+                        ..info
+                    },
+                ),
+                None => return info,
+            }
+        }
+        None => return Info { len: 0, typ: None },
+    };
+
+    let mut len = info.len;
+    for label in labels {
+        entry = match trie.child(&entry, label) {
+            Some(entry) => entry,
+            None => match trie.child(&entry, WILDCARD.as_bytes()) {
+                Some(entry) => entry,
+                None => break,
+            },
+        };
+        let with_label = len + 1 + label.len();
+        if entry.typ.is_some() {
+            info.typ = entry.typ;
+            if entry.exception {
+                info.len = len;
+                break;
+            }
+            info.len = with_label;
+        }
+        len = with_label;
+    }
+
+    info
+}
+
+/// A label in a compiled list
+///
+/// The children of an entry are `count` consecutive entries starting at
+/// index `first`, sorted by their labels.
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Entry<'a> {
+    label: &'a [u8],
+    first: u32,
+    count: u32,
+    typ: Option<Type>,
+    exception: bool,
+}
+
+impl<'a> Entry<'a> {
+    /// A label that only leads to other rules
+    pub const fn branch(label: &'a [u8], first: u32, count: u32) -> Self {
+        Self {
+            label,
+            first,
+            count,
+            typ: None,
+            exception: false,
+        }
+    }
+
+    /// A label ending an ICANN rule
+    pub const fn icann(label: &'a [u8], first: u32, count: u32) -> Self {
+        Self {
+            typ: Some(Type::Icann),
+            ..Self::branch(label, first, count)
+        }
+    }
+
+    /// A label ending a PRIVATE rule
+    pub const fn private(label: &'a [u8], first: u32, count: u32) -> Self {
+        Self {
+            typ: Some(Type::Private),
+            ..Self::branch(label, first, count)
+        }
+    }
+
+    /// Makes the rule ending here an exception rule
+    pub const fn exception(mut self) -> Self {
+        self.exception = true;
+        self
+    }
+
+    /// The label, as it appears in names
+    pub const fn label(&self) -> &'a [u8] {
+        self.label
+    }
+
+    /// The index of the first child
+    pub const fn first(&self) -> u32 {
+        self.first
+    }
+
+    /// The number of children
+    pub const fn count(&self) -> u32 {
+        self.count
+    }
+
+    /// The type of the rule ending here, if any
+    pub const fn typ(&self) -> Option<Type> {
+        self.typ
+    }
+
+    /// Whether the rule ending here is an exception rule
+    pub const fn is_exception(&self) -> bool {
+        self.exception
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl Rules {
+    /// Lays the rules out as the entries of a `Compiled` list
+    pub(crate) fn entries(&self) -> Vec<Entry<'_>> {
+        let mut entries = Vec::new();
+        entries.push(Entry::branch(b"", 0, 0));
+        let mut queue = VecDeque::new();
+        queue.push_back((0, &self.root));
+        while let Some((index, node)) = queue.pop_front() {
+            entries[index].first = entries.len() as u32;
+            entries[index].count = node.children.len() as u32;
+            for (label, child) in &node.children {
+                let entry = match child.rule.map(|index| &self.rules[index]) {
+                    Some(rule) if rule.typ() == Type::Private => Entry::private(label, 0, 0),
+                    Some(_) => Entry::icann(label, 0, 0),
+                    None => Entry::branch(label, 0, 0),
+                };
+                let exception = child
+                    .rule
+                    .map_or(false, |index| self.rules[index].is_exception());
+                entries.push(if exception { entry.exception() } else { entry });
+                queue.push_back((entries.len() - 1, child));
+            }
+        }
+        entries
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{is_suffix, parse_rules};
+    use crate::parser::DomainName;
+
+    #[cfg(feature = "std")]
+    #[test]
+    fn compiled() {
+        let dat = include_str!("../../tests/public_suffix_list.dat");
+        let rules = parse_rules(dat).unwrap();
+        let entries = rules.entries();
+        let list = Compiled::new(&entries, rules.metadata());
+        for rule in rules.iter() {
+            let name = rule.name().replace('*', "wildcard");
+            let name = format!("www.example.{}", name);
+            assert_eq!(
+                list.find(name.rsplit('.').map(str::as_bytes)),
+                rules.find(name.rsplit('.').map(str::as_bytes)),
+                "{}",
+                name
+            );
+        }
+        assert!(is_suffix(&list, "co.uk"));
+        assert!(!is_suffix(&Compiled::new(&[], Metadata::new()), "co.uk"));
+    }
+
+    #[test]
+    fn enumerate() {
+        let dat = include_str!("../../tests/public_suffix_list.dat");
+        let rules = parse_rules(dat).unwrap();
+        let entries = rules.entries();
+        let list = Compiled::new(&entries, rules.metadata()).to_rules();
+        assert_eq!(list.len(), rules.len());
+        assert_eq!(list.metadata(), rules.metadata());
+        for rule in rules.iter() {
+            assert_eq!(list.get(rule.as_str()), Some(rule));
+        }
+        assert!(Compiled::new(&[], Metadata::new()).to_rules().is_empty());
+        assert!(is_suffix(&list, "xn--fiqs8s"));
+
+        // `uk` is its own child
+        let entries = [Entry::branch(b"", 1, 1), Entry::icann(b"uk", 1, 1)];
+        let list = Compiled::new(&entries, Metadata::new()).to_rules();
+        assert!(list.get("uk").is_some());
+        assert_eq!(list.len(), MAX_LABELS);
+    }
+
+    #[test]
+    fn a_labels() {
+        let rules = parse_rules("中国\n公司.香港").unwrap();
+        let entries = rules.entries();
+        let list = Compiled::new(&entries, rules.metadata());
+        let name = list.parse_domain_name("www.example.xn--fiqs8s").unwrap();
+        assert!(name.has_known_suffix());
+        assert!(is_suffix(&list, "xn--55qx5d.xn--j6w193g"));
+        assert!(is_suffix(&list, "公司.香港"));
+    }
+}
@@ -0,0 +1,226 @@
+use super::{Changes, Rules};
+use crate::error::Kind;
+use core::fmt;
+use psl_types::{Info, List};
+use std::{
+    fs, io,
+    path::Path,
+    sync::{Arc, Mutex, PoisonError, RwLock},
+};
+
+type Callback = Arc<dyn Fn(&Changes) + Send + Sync>;
+
+/// A list that can be replaced while it is being used
+///
+/// Lookups use whichever rules are current when they start, so a reload
+/// never blocks readers for longer than it takes to swap a pointer.
+/// Callbacks registered with `on_change` are told which rules changed.
+///
+/// ```rust
+/// use addr::list::{parse_rules, Handle};
+/// use addr::parser::DomainName;
+/// use std::sync::Arc;
+///
+/// let list = Arc::new(Handle::new(parse_rules("com\nuk").unwrap()));
+/// list.on_change(|changes| println!("{} rules added", changes.added().len()));
+///
+/// let domain = list.parse_domain_name("www.example.co.uk").unwrap();
+/// assert_eq!(domain.root(), Some("co.uk"));
+///
+/// let changes = list.reload("com\nuk\nco.uk").unwrap();
+/// assert_eq!(changes.added().len(), 1);
+///
+/// let domain = list.parse_domain_name("www.example.co.uk").unwrap();
+/// assert_eq!(domain.root(), Some("example.co.uk"));
+/// ```
+pub struct Handle {
+    rules: RwLock<Arc<Rules>>,
+    // a reload runs the callbacks registered when it replaced the rules
+    callbacks: Mutex<Arc<Vec<Callback>>>,
+    reloading: Mutex<()>,
+}
+
+impl Handle {
+    /// Creates a handle to `rules`
+    pub fn new(rules: Rules) -> Self {
+        Self {
+            rules: RwLock::new(Arc::new(rules)),
+            callbacks: Mutex::new(Arc::new(Vec::new())),
+            reloading: Mutex::new(()),
+        }
+    }
+
+    /// The current rules
+    ///
+    /// They are unaffected by later reloads, so several lookups can be
+    /// made against the same version of the list.
+    pub fn rules(&self) -> Arc<Rules> {
+        let rules = self.rules.read().unwrap_or_else(PoisonError::into_inner);
+        Arc::clone(&rules)
+    }
+
+    /// Calls `f` with the changes every time the rules are replaced
+    ///
+    /// Callbacks run on the thread doing the reload, after the rules are
+    /// replaced and without holding any locks, so they may reload the list
+    /// or register more callbacks. Those only apply from the next reload.
+    pub fn on_change<F>(&self, f: F)
+    where
+        F: Fn(&Changes) + Send + Sync + 'static,
+    {
+        let mut callbacks = self
+            .callbacks
+            .lock()
+            .unwrap_or_else(PoisonError::into_inner);
+        Arc::make_mut(&mut callbacks).push(Arc::new(f));
+    }
+
+    /// Replaces the rules, returning what changed
+    pub fn replace(&self, rules: Rules) -> Changes {
+        let (changes, callbacks) = {
+            // reloads replace the rules one at a time
+            let _reloading = self
+                .reloading
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            let new = Arc::new(rules);
+            let old = {
+                let mut current = self.rules.write().unwrap_or_else(PoisonError::into_inner);
+                core::mem::replace(&mut *current, Arc::clone(&new))
+            };
+            let callbacks = self
+                .callbacks
+                .lock()
+                .unwrap_or_else(PoisonError::into_inner);
+            (Changes::between(&old, &new), Arc::clone(&callbacks))
+        };
+        if !changes.is_empty() {
+            for callback in callbacks.iter() {
+                callback(&changes);
+            }
+        }
+        changes
+    }
+
+    /// Parses a new list and replaces the rules with it
+    ///
+    /// The current rules are kept if the list is invalid.
+    pub fn reload<'a>(&self, input: &'a str) -> crate::Result<'a, Changes> {
+        let rules = Rules::parse(input)?;
+        Ok(self.replace(rules))
+    }
+
+    /// Like `reload`, for lists that are not known to be UTF-8
+    ///
+    /// Invalid UTF-8 is reported as an illegal character at the end of
+    /// the valid part of the input.
+    pub fn reload_bytes<'a>(&self, bytes: &'a [u8]) -> crate::Result<'a, Changes> {
+        match core::str::from_utf8(bytes) {
+            Ok(input) => self.reload(input),
+            Err(error) => {
+                let valid = error.valid_up_to();
+                let input = core::str::from_utf8(&bytes[..valid]).unwrap_or_default();
+                Err(Kind::IllegalCharacter.at(valid, valid).error_with(input))
+            }
+        }
+    }
+
+    /// Reads a new list from a file and replaces the rules with it
+    ///
+    /// An invalid list is reported as `InvalidData`, wrapping the
+    /// `OwnedError` that describes it.
+    pub fn reload_file<P: AsRef<Path>>(&self, path: P) -> io::Result<Changes> {
+        let bytes = fs::read(path)?;
+        self.reload_bytes(&bytes)
+            .map_err(|error| io::Error::new(io::ErrorKind::InvalidData, error.into_owned()))
+    }
+}
+
+impl List for Handle {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        self.rules().find(labels)
+    }
+}
+
+impl fmt::Debug for Handle {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.debug_struct("Handle")
+            .field("rules", &self.rules().len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{parse_rules, LIST};
+    use crate::parser::DomainName;
+
+    #[test]
+    fn reloads() {
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::{sync::Arc, thread};
+
+        let list = Arc::new(Handle::new(parse_rules("com").unwrap()));
+        let calls = Arc::new(AtomicUsize::new(0));
+        let counter = Arc::clone(&calls);
+        list.on_change(move |changes| {
+            counter.fetch_add(changes.added().len(), Ordering::SeqCst);
+        });
+
+        let before = list.rules();
+        let reader = {
+            let list = Arc::clone(&list);
+            thread::spawn(move || {
+                for _ in 0..100 {
+                    let name = list.parse_domain_name("www.example.co.uk").unwrap();
+                    let root = name.root().unwrap();
+                    assert!(root == "co.uk" || root == "example.co.uk");
+                }
+            })
+        };
+        list.reload(LIST).unwrap();
+        reader.join().unwrap();
+
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+        assert_eq!(before.len(), 1);
+        let name = list.parse_domain_name("www.example.co.uk").unwrap();
+        assert_eq!(name.root(), Some("example.co.uk"));
+
+        // nothing changed, so the callbacks are not called
+        assert!(list.reload(LIST).unwrap().is_empty());
+        assert_eq!(calls.load(Ordering::SeqCst), 6);
+
+        // invalid lists leave the rules as they were
+        let error = list.reload("com\nco..uk").unwrap_err();
+        assert_eq!(error.kind(), Kind::EmptyLabel);
+        let error = list.reload_bytes(b"com\n\xffuk").unwrap_err();
+        assert_eq!(error.kind(), Kind::IllegalCharacter);
+        assert_eq!(error.span(), Some(4..4));
+        let error = list
+            .reload_file("/nonexistent/public_suffix_list.dat")
+            .unwrap_err();
+        assert_eq!(error.kind(), std::io::ErrorKind::NotFound);
+        assert_eq!(list.rules().len(), 7);
+    }
+
+    #[test]
+    fn reentrant_callbacks() {
+        let list = Arc::new(Handle::new(parse_rules("com").unwrap()));
+        let handle = Arc::downgrade(&list);
+        // undoes any reload that adds `uk`
+        list.on_change(move |changes| {
+            let list = handle.upgrade().unwrap();
+            if changes.added().iter().any(|rule| rule.as_str() == "uk") {
+                list.on_change(|_| {});
+                list.reload("com").unwrap();
+            }
+        });
+        let changes = list.reload("com\nuk").unwrap();
+        assert_eq!(changes.added().len(), 1);
+        assert_eq!(list.rules().len(), 1);
+    }
+}
@@ -0,0 +1,260 @@
+use core::fmt;
+
+/// Which version of the public suffix list a list was built from
+///
+/// `public_suffix_list.dat` starts with `// VERSION:` and `// COMMIT:`
+/// comments. The version is the time the file was generated, e.g.
+/// `2024-06-25_10-05-23_UTC`, and the commit is the one in the
+/// `publicsuffix/list` repository it was generated from.
+///
+/// Every list reports it through `Versioned`. `Rules`, `Compiled` and
+/// `Binary` lists keep it from the list they were built from. `psl::List`
+/// has no header to keep, so it reports the version of the `psl` crate
+/// instead, which this crate pins, and `publicsuffix::Parsed` keeps the
+/// header of the input it was parsed from.
+///
+/// ```rust
+/// use addr::list::Metadata;
+///
+/// let metadata = Metadata::parse(
+///     "// VERSION: 2024-06-25_10-05-23_UTC
+///     // COMMIT: 7e6b5e5e3e0b1c0a2d6a0e6c8b8f6e4f2c1a0b9d
+///     com",
+/// );
+/// assert_eq!(metadata.version(), Some("2024-06-25_10-05-23_UTC"));
+/// assert_eq!(metadata.date().unwrap().to_string(), "2024-06-25T10:05:23Z");
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Metadata<'a> {
+    version: Option<&'a str>,
+    commit: Option<&'a str>,
+    package: Option<&'a str>,
+}
+
+impl<'a> Metadata<'a> {
+    /// Metadata that records nothing
+    pub const fn new() -> Self {
+        Self {
+            version: None,
+            commit: None,
+            package: None,
+        }
+    }
+
+    /// Metadata with the given version and commit
+    pub const fn from_parts(version: Option<&'a str>, commit: Option<&'a str>) -> Self {
+        Self {
+            version,
+            commit,
+            package: None,
+        }
+    }
+
+    #[cfg(feature = "psl")]
+    pub(crate) const fn with_package(self, package: &'a str) -> Self {
+        Self {
+            package: Some(package),
+            ..self
+        }
+    }
+
+    /// Reads the header comments of a list, stopping at its first rule
+    pub fn parse(input: &'a str) -> Self {
+        let mut metadata = Self::new();
+        for line in input.lines() {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if !line.starts_with("//") {
+                break;
+            }
+            let comment = line.trim_start_matches('/').trim();
+            if let Some(value) = field(comment, "VERSION:") {
+                metadata.version = Some(value);
+            } else if let Some(value) = field(comment, "COMMIT:") {
+                metadata.commit = Some(value);
+            }
+        }
+        metadata
+    }
+
+    /// The version of the list, e.g. `2024-06-25_10-05-23_UTC`
+    pub const fn version(&self) -> Option<&'a str> {
+        self.version
+    }
+
+    /// The commit the list was generated from
+    pub const fn commit(&self) -> Option<&'a str> {
+        self.commit
+    }
+
+    /// The crate the list was compiled into, e.g. `psl 2.1.241`
+    pub const fn package(&self) -> Option<&'a str> {
+        self.package
+    }
+
+    /// When the list was generated, if the version says so
+    pub fn date(&self) -> Option<Timestamp> {
+        Timestamp::parse(self.version?)
+    }
+}
+
+/// A list that knows which version of the public suffix list it holds
+///
+/// Services can log this to record which suffix data they used, whichever
+/// list backs them.
+///
+/// ```rust
+/// use addr::list::{parse_rules, Versioned};
+///
+/// let rules = parse_rules("// VERSION: 2024-06-25_10-05-23_UTC\ncom").unwrap();
+/// assert_eq!(Versioned::metadata(&rules).version(), Some("2024-06-25_10-05-23_UTC"));
+/// ```
+pub trait Versioned {
+    /// The version of the list
+    fn metadata(&self) -> Metadata<'_>;
+}
+
+fn field<'a>(comment: &'a str, name: &str) -> Option<&'a str> {
+    if !comment.starts_with(name) {
+        return None;
+    }
+    let value = comment.get(name.len()..)?.trim();
+    if value.is_empty() {
+        None
+    } else {
+        Some(value)
+    }
+}
+
+/// A point in time, in UTC
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Timestamp {
+    year: u16,
+    month: u8,
+    day: u8,
+    hour: u8,
+    minute: u8,
+    second: u8,
+}
+
+impl Timestamp {
+    // `2024-06-25_10-05-23_UTC`
+    fn parse(version: &str) -> Option<Self> {
+        let part = |start: usize, end: usize| version.get(start..end)?.parse::<u8>().ok();
+        if version.len() != 23 || version.get(19..) != Some("_UTC") {
+            return None;
+        }
+        let timestamp = Self {
+            year: version.get(..4)?.parse().ok()?,
+            month: part(5, 7)?,
+            day: part(8, 10)?,
+            hour: part(11, 13)?,
+            minute: part(14, 16)?,
+            second: part(17, 19)?,
+        };
+        let separators = [(4, b'-'), (7, b'-'), (10, b'_'), (13, b'-'), (16, b'-')];
+        let valid = (1..=12).contains(&timestamp.month)
+            && (1..=31).contains(&timestamp.day)
+            && timestamp.hour < 24
+            && timestamp.minute < 60
+            && timestamp.second < 61
+            && separators
+                .iter()
+                .all(|&(index, byte)| version.as_bytes()[index] == byte);
+        if valid {
+            Some(timestamp)
+        } else {
+            None
+        }
+    }
+
+    /// The year, e.g. 2024
+    pub const fn year(&self) -> u16 {
+        self.year
+    }
+
+    /// The month, from 1 to 12
+    pub const fn month(&self) -> u8 {
+        self.month
+    }
+
+    /// The day of the month, from 1 to 31
+    pub const fn day(&self) -> u8 {
+        self.day
+    }
+
+    /// The hour, from 0 to 23
+    pub const fn hour(&self) -> u8 {
+        self.hour
+    }
+
+    /// The minute, from 0 to 59
+    pub const fn minute(&self) -> u8 {
+        self.minute
+    }
+
+    /// The second, from 0 to 60 for leap seconds
+    pub const fn second(&self) -> u8 {
+        self.second
+    }
+}
+
+/// Formats the timestamp as RFC 3339, e.g. `2024-06-25T10:05:23Z`
+impl fmt::Display for Timestamp {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{:04}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            self.year, self.month, self.day, self.hour, self.minute, self.second
+        )
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{parse_rules, LIST};
+
+    #[test]
+    fn metadata() {
+        let input = "// This Source Code Form is subject to the terms of the Mozilla Public
+
+            // VERSION: 2024-06-25_10-05-23_UTC
+            // COMMIT: 7e6b5e5e3e0b1c0a2d6a0e6c8b8f6e4f2c1a0b9d
+
+            // ===BEGIN ICANN DOMAINS===
+            com
+            // VERSION: ignored after the first rule
+        ";
+        let metadata = Metadata::parse(input);
+        assert_eq!(metadata.version(), Some("2024-06-25_10-05-23_UTC"));
+        assert_eq!(
+            metadata.commit(),
+            Some("7e6b5e5e3e0b1c0a2d6a0e6c8b8f6e4f2c1a0b9d")
+        );
+        let date = metadata.date().unwrap();
+        assert_eq!((date.year(), date.month(), date.day()), (2024, 6, 25));
+        assert_eq!((date.hour(), date.minute(), date.second()), (10, 5, 23));
+        assert_eq!(parse_rules(input).unwrap().metadata(), metadata);
+
+        assert_eq!(Metadata::parse(LIST), Metadata::new());
+        assert_eq!(parse_rules(LIST).unwrap().metadata().version(), None);
+        for version in &["2024-13-25_10-05-23_UTC", "2024-06-25 10:05:23", "v1"] {
+            assert!(Timestamp::parse(version).is_none(), "{}", version);
+        }
+    }
+
+    #[cfg(feature = "psl")]
+    #[test]
+    fn psl() {
+        let metadata = crate::psl::List.metadata();
+        assert_eq!(metadata.version(), None);
+        let package = metadata.package().unwrap();
+        // the pinned version in the manifest
+        let version = package.trim_start_matches("psl ");
+        let manifest = include_str!("../../Cargo.toml");
+        assert!(manifest.contains(&format!("version = \"={}\"", version)));
+    }
+}
@@ -0,0 +1,139 @@
+//! Public suffix list rules
+//!
+//! `Rules` holds the rules themselves, so they can be listed and counted.
+//! It is parsed from the same `public_suffix_list.dat` format and is a
+//! `List` too, so it can be used with the `parser` traits in place of any
+//! other list. `Compiled` and `Binary` lists can be read back into `Rules`
+//! with their `to_rules` methods.
+//!
+//! `publicsuffix::Parsed` keeps its input, so its `to_rules` parses the
+//! same rules. `psl::List` can't be enumerated: the `psl` crate compiles
+//! its rules into lookup code and doesn't ship the list it was generated
+//! from. To list them, parse the `public_suffix_list.dat` matching the
+//! `psl` version in `Versioned::metadata` with `parse_rules`, or build the
+//! list with the `build` module instead.
+//!
+//! ```rust
+//! use addr::list::{self, parse_rules};
+//! use addr::parser::DomainName;
+//! use psl_types::Type;
+//!
+//! let rules = parse_rules(
+//!     "// ===BEGIN ICANN DOMAINS===
+//!     uk
+//!     co.uk
+//!     *.sch.uk
+//!     // ===BEGIN PRIVATE DOMAINS===
+//!     blogspot.co.uk",
+//! )
+//! .unwrap();
+//!
+//! assert_eq!(rules.len(), 4);
+//! assert_eq!(rules.under("uk").count(), 4);
+//! assert_eq!(rules.iter().filter(|rule| rule.typ() == Type::Private).count(), 1);
+//! assert!(list::is_suffix(&rules, "school.sch.uk"));
+//!
+//! let domain = rules.parse_domain_name("www.example.co.uk").unwrap();
+//! assert_eq!(domain.root(), Some("example.co.uk"));
+//! ```
+
+mod binary;
+#[cfg(feature = "alloc")]
+mod changes;
+mod compiled;
+#[cfg(feature = "std")]
+mod handle;
+mod metadata;
+#[cfg(feature = "alloc")]
+mod overlay;
+#[cfg(feature = "alloc")]
+mod rules;
+mod section;
+
+pub use binary::Binary;
+#[cfg(feature = "alloc")]
+pub use changes::{affected, Affected, Changes};
+pub use compiled::{Compiled, Entry};
+#[cfg(feature = "std")]
+pub use handle::Handle;
+pub use metadata::{Metadata, Timestamp, Versioned};
+#[cfg(feature = "alloc")]
+pub use overlay::Overlay;
+#[cfg(feature = "alloc")]
+pub use rules::{parse_rules, Rule, Rules};
+pub use section::Section;
+
+use psl_types::List;
+
+// a name has at most 127 labels
+const MAX_LABELS: usize = 127;
+
+const WILDCARD: &str = "*";
+
+/// Whether `name` is exactly a public suffix in the list
+///
+/// This works with any list, including the compiled one. Names covered by
+/// a wildcard rule are suffixes too, unless an exception rule says
+/// otherwise.
+pub fn is_suffix<T: List + ?Sized>(list: &T, name: &str) -> bool {
+    match list.suffix(name.as_bytes()) {
+        Some(suffix) => suffix.is_known() && suffix == name,
+        None => false,
+    }
+}
+
+// the number of labels in a suffix that is `len` bytes long
+fn suffix_labels(labels: &[&[u8]], len: usize) -> usize {
+    let mut count = 0;
+    let mut total = 0;
+    while total < len && count < labels.len() {
+        total += labels[count].len() + (count > 0) as usize;
+        count += 1;
+    }
+    count
+}
+
+// the list most tests use
+#[cfg(test)]
+const LIST: &str = "
+    // ===BEGIN ICANN DOMAINS===
+    com
+    uk
+    co.uk
+    *.ck
+    !www.ck
+    // ===BEGIN PRIVATE DOMAINS===
+    github.io
+    blogspot.co.uk // trailing comments are ignored
+";
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::DomainName;
+
+    #[test]
+    fn suffixes() {
+        let rules = parse_rules(LIST).unwrap();
+        assert!(is_suffix(&rules, "co.uk"));
+        assert!(is_suffix(&rules, "github.io"));
+        assert!(is_suffix(&rules, "anything.ck"));
+        assert!(!is_suffix(&rules, "www.ck"));
+        assert!(!is_suffix(&rules, "example.com"));
+        assert!(!is_suffix(&rules, "io"));
+
+        assert!(is_suffix(&psl::List, "co.uk"));
+        assert!(!is_suffix(&psl::List, "example.co.uk"));
+
+        let name = rules
+            .parse_domain_name("www.example.blogspot.co.uk")
+            .unwrap();
+        assert_eq!(name.suffix(), "blogspot.co.uk");
+        assert!(name.is_private());
+        let name = rules.parse_domain_name("www.ck").unwrap();
+        assert_eq!(name.suffix(), "ck");
+        let name = rules.parse_domain_name("example.io.").unwrap();
+        assert_eq!(name.suffix(), "io.");
+        assert!(!name.has_known_suffix());
+    }
+}
@@ -0,0 +1,223 @@
+use super::{suffix_labels, Rules, WILDCARD};
+use alloc::vec::Vec;
+use psl_types::{Info, List, Type};
+
+/// Rules added to and removed from another list
+///
+/// Added rules take precedence over the rules of the base list when they
+/// match as many labels, so they can also change the type of a suffix.
+/// When a rule of the base list is removed, the next shorter rule that
+/// matches is used instead, and removing an exception rule like `!www.ck`
+/// lets the wildcard rule it was an exception to match again. Overlays
+/// are lists too, so they can be layered on top of each other.
+///
+/// ```rust
+/// use addr::list::Overlay;
+/// use addr::parser::DomainName;
+/// use psl_types::Type;
+///
+/// let mut list = Overlay::new(psl::List);
+/// list.add("corp.example", Type::Private).unwrap();
+/// list.remove("github.io").unwrap();
+///
+/// let domain = list.parse_domain_name("www.intranet.corp.example").unwrap();
+/// assert_eq!(domain.root(), Some("intranet.corp.example"));
+///
+/// let domain = list.parse_domain_name("example.github.io").unwrap();
+/// assert_eq!(domain.root(), Some("github.io"));
+/// ```
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Overlay<L> {
+    base: L,
+    added: Rules,
+    removed: Rules,
+}
+
+impl<L: List> Overlay<L> {
+    /// Creates an overlay without any changes to `base`
+    pub fn new(base: L) -> Self {
+        Self {
+            base,
+            added: Rules::new(),
+            removed: Rules::new(),
+        }
+    }
+
+    /// Adds a rule, e.g. `corp.example`, `*.hosting.example` or
+    /// `!www.hosting.example`, to the given section
+    pub fn add<'a>(&mut self, rule: &'a str, typ: Type) -> crate::Result<'a, ()> {
+        self.removed.remove(rule);
+        self.added.insert(rule, typ)
+    }
+
+    /// Removes a rule, e.g. `github.io`, from the base list
+    ///
+    /// Suffixes matched by the rule fall back to the next shorter rule.
+    pub fn remove<'a>(&mut self, rule: &'a str) -> crate::Result<'a, ()> {
+        self.added.remove(rule);
+        self.removed.insert(rule, Type::Icann)
+    }
+
+    /// The list the changes are applied to
+    pub fn base(&self) -> &L {
+        &self.base
+    }
+
+    /// The rules added to the base list
+    pub fn added(&self) -> &Rules {
+        &self.added
+    }
+
+    /// The rules removed from the base list
+    ///
+    /// Their types are meaningless.
+    pub fn removed(&self) -> &Rules {
+        &self.removed
+    }
+
+    fn find_in_base(&self, labels: &[&[u8]]) -> Info {
+        let info = self.find_in_rules(labels);
+        let (removed, rule) = self.removed.lookup(labels.iter().copied());
+        match rule {
+            Some(rule) if rule.is_exception() && removed.len == info.len => {
+                // without the exception, its label only matches a wildcard
+                let index = suffix_labels(labels, removed.len);
+                let mut labels = labels.to_vec();
+                let label = core::mem::replace(&mut labels[index], WILDCARD.as_bytes());
+                let info = self.find_in_rules(&labels);
+                if suffix_labels(&labels, info.len) > index {
+                    Info {
+                        len: info.len - WILDCARD.len() + label.len(),
+                        ..info
+                    }
+                } else {
+                    info
+                }
+            }
+            _ => info,
+        }
+    }
+
+    // skips the rules that were removed, other than exception rules
+    fn find_in_rules(&self, labels: &[&[u8]]) -> Info {
+        let mut count = labels.len();
+        loop {
+            let info = self.base.find(labels[..count].iter().copied());
+            if info.typ.is_none() {
+                return info;
+            }
+            let found = suffix_labels(labels, info.len);
+            let removed = self.removed.find(labels[..found].iter().copied());
+            if removed.typ.is_none() || removed.len != info.len {
+                return info;
+            }
+            count = found - 1;
+            if count == 0 {
+                return Info {
+                    len: labels[0].len(),
+                    typ: None,
+                };
+            }
+        }
+    }
+}
+
+impl<L: List> List for Overlay<L> {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        let labels = labels.collect::<Vec<_>>();
+        if labels.is_empty() {
+            return Info { len: 0, typ: None };
+        }
+        let base = self.find_in_base(&labels);
+        let (added, rule) = self.added.lookup(labels.iter().copied());
+        match rule {
+            Some(rule) if rule.is_exception() || added.len >= base.len || base.typ.is_none() => {
+                added
+            }
+            _ => base,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{parse_rules, LIST};
+    use crate::parser::DomainName;
+
+    #[test]
+    fn overlays() {
+        let mut list = Overlay::new(psl::List);
+        list.add("corp.example", Type::Private).unwrap();
+        list.add("*.hosting.example", Type::Private).unwrap();
+        list.add("!www.hosting.example", Type::Private).unwrap();
+        list.remove("github.io").unwrap();
+
+        let name = list.parse_domain_name("a.b.corp.example").unwrap();
+        assert_eq!(name.suffix(), "corp.example");
+        assert!(name.is_private());
+        let name = list.parse_domain_name("a.b.hosting.example").unwrap();
+        assert_eq!(name.root(), Some("a.b.hosting.example"));
+        let name = list.parse_domain_name("www.hosting.example").unwrap();
+        assert_eq!(name.suffix(), "hosting.example");
+
+        // falls back to the next shorter rule
+        let name = list.parse_domain_name("example.github.io").unwrap();
+        assert_eq!(name.suffix(), "io");
+        assert!(name.is_icann());
+
+        // untouched rules still apply
+        let name = list.parse_domain_name("www.example.co.uk").unwrap();
+        assert_eq!(name.root(), Some("example.co.uk"));
+
+        // an added rule can change the type of an existing one
+        list.add("co.uk", Type::Private).unwrap();
+        assert!(list
+            .parse_domain_name("example.co.uk")
+            .unwrap()
+            .is_private());
+
+        // re-adding a removed rule restores it
+        list.add("github.io", Type::Private).unwrap();
+        assert!(list.removed().is_empty());
+        let name = list.parse_domain_name("example.github.io").unwrap();
+        assert_eq!(name.root(), Some("example.github.io"));
+    }
+
+    #[test]
+    fn layered_overlays() {
+        let rules = parse_rules(LIST).unwrap();
+        let mut inner = Overlay::new(rules);
+        inner.remove("co.uk").unwrap();
+        let mut outer = Overlay::new(inner);
+        outer.add("example.uk", Type::Private).unwrap();
+
+        let name = outer.parse_domain_name("www.example.co.uk").unwrap();
+        assert_eq!(name.suffix(), "uk");
+        let name = outer.parse_domain_name("www.foo.example.uk").unwrap();
+        assert_eq!(name.root(), Some("foo.example.uk"));
+        assert_eq!(outer.base().added().len(), 0);
+    }
+
+    #[test]
+    fn removed_exception() {
+        let mut rules = Overlay::new(parse_rules(LIST).unwrap());
+        rules.remove("!www.ck").unwrap();
+        let name = rules.parse_domain_name("www.ck").unwrap();
+        assert_eq!(name.suffix(), "www.ck");
+        let name = rules.parse_domain_name("example.www.ck").unwrap();
+        assert_eq!(name.root(), Some("example.www.ck"));
+        let name = rules.parse_domain_name("example.foo.ck").unwrap();
+        assert_eq!(name.root(), Some("example.foo.ck"));
+
+        let mut list = Overlay::new(psl::List);
+        assert_eq!(list.parse_domain_name("www.ck").unwrap().suffix(), "ck");
+        list.remove("!www.ck").unwrap();
+        let name = list.parse_domain_name("example.www.ck").unwrap();
+        assert_eq!(name.suffix(), "www.ck");
+        assert!(name.is_icann());
+    }
+}
@@ -0,0 +1,465 @@
+use super::{Metadata, Versioned, WILDCARD};
+use crate::error::Kind;
+use crate::punycode;
+use alloc::{collections::BTreeMap, string::String, vec::Vec};
+use core::fmt;
+use psl_types::{Info, List, Type};
+
+const EXCEPTION: char = '!';
+
+/// A single rule in the list
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Rule {
+    text: String,
+    typ: Type,
+}
+
+impl Rule {
+    /// The rule as written in the list, e.g. `*.ck` or `!www.ck`
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// The rule without the `!` of an exception
+    pub fn name(&self) -> &str {
+        self.text.trim_start_matches(EXCEPTION)
+    }
+
+    /// The top level domain the rule is under
+    pub fn tld(&self) -> &str {
+        self.text.rsplit('.').next().unwrap_or_default()
+    }
+
+    /// Whether the rule starts with a `*` label
+    pub fn is_wildcard(&self) -> bool {
+        self.name().split('.').next() == Some(WILDCARD)
+    }
+
+    /// Whether the rule is an exception to a wildcard rule
+    pub fn is_exception(&self) -> bool {
+        self.text.starts_with(EXCEPTION)
+    }
+
+    /// Whether this is an ICANN or a PRIVATE rule
+    pub const fn typ(&self) -> Type {
+        self.typ
+    }
+}
+
+impl fmt::Display for Rule {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// The rules of a public suffix list
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct Rules {
+    pub(super) rules: Vec<Rule>,
+    pub(super) root: Node,
+    pub(super) version: Option<String>,
+    pub(super) commit: Option<String>,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub(super) struct Node {
+    pub(super) children: BTreeMap<Vec<u8>, Node>,
+    // the index of the rule ending here
+    pub(super) rule: Option<usize>,
+}
+
+impl Node {
+    // removes the rule at `index` and shifts the indexes of later rules
+    fn forget(&mut self, name: &str, index: usize) {
+        self.forget_labels(&mut name.rsplit('.'), index);
+        if let Some(name) = to_ascii(name) {
+            self.forget_labels(&mut name.rsplit('.'), index);
+        }
+        self.shift(index);
+    }
+
+    fn forget_labels<'a>(&mut self, labels: &mut impl Iterator<Item = &'a str>, index: usize) {
+        match labels.next() {
+            Some(label) => {
+                if let Some(child) = self.children.get_mut(label.as_bytes()) {
+                    child.forget_labels(labels, index);
+                    if child.rule.is_none() && child.children.is_empty() {
+                        self.children.remove(label.as_bytes());
+                    }
+                }
+            }
+            None => {
+                if self.rule == Some(index) {
+                    self.rule = None;
+                }
+            }
+        }
+    }
+
+    fn shift(&mut self, index: usize) {
+        if let Some(rule) = self.rule.as_mut() {
+            if *rule > index {
+                *rule -= 1;
+            }
+        }
+        for child in self.children.values_mut() {
+            child.shift(index);
+        }
+    }
+}
+
+impl Rules {
+    /// Creates an empty list
+    ///
+    /// Like any list, it still treats the last label of a name as its
+    /// suffix.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn parse(input: &str) -> crate::Result<'_, Rules> {
+        let mut rules = Self::new();
+        let metadata = Metadata::parse(input);
+        rules.version = metadata.version().map(Into::into);
+        rules.commit = metadata.commit().map(Into::into);
+        let mut typ = Type::Icann;
+        for line in input.lines() {
+            let line = line.trim();
+            if line.starts_with("//") {
+                if line.contains("BEGIN ICANN DOMAINS") {
+                    typ = Type::Icann;
+                } else if line.contains("BEGIN PRIVATE DOMAINS") {
+                    typ = Type::Private;
+                }
+                continue;
+            }
+            // rules are only read up to the first whitespace
+            if let Some(rule) = line.split_whitespace().next() {
+                rules.insert(rule, typ)?;
+            }
+        }
+        Ok(rules)
+    }
+
+    /// The version and commit of the list, from its header comments
+    pub fn metadata(&self) -> Metadata<'_> {
+        Metadata::from_parts(self.version.as_deref(), self.commit.as_deref())
+    }
+
+    /// Adds a rule, or changes the type of an existing one
+    ///
+    /// Rules with non-ASCII labels, like `中国`, also match names written
+    /// with their A-labels, like `xn--fiqs8s`.
+    pub fn insert<'a>(&mut self, rule: &'a str, typ: Type) -> crate::Result<'a, ()> {
+        let name = check(rule).map_err(|cause| cause.error_with(rule))?;
+        let rule = Rule {
+            text: rule.into(),
+            typ,
+        };
+        let mut node = &mut self.root;
+        for label in name.rsplit('.') {
+            node = node.children.entry(label.as_bytes().into()).or_default();
+        }
+        // the node may only be the A-label form of another rule
+        let index = match node.rule {
+            Some(index) if self.rules[index].name() == name => {
+                self.rules[index] = rule;
+                index
+            }
+            _ => {
+                node.rule = Some(self.rules.len());
+                self.rules.push(rule);
+                self.rules.len() - 1
+            }
+        };
+        if let Some(ascii) = to_ascii(name) {
+            self.alias(&ascii, index);
+        }
+        Ok(())
+    }
+
+    // makes the A-label form of a rule match it, unless a rule ends there
+    fn alias(&mut self, ascii: &str, index: usize) {
+        let mut node = &mut self.root;
+        for label in ascii.rsplit('.') {
+            node = node.children.entry(label.as_bytes().into()).or_default();
+        }
+        if node.rule.is_none() {
+            node.rule = Some(index);
+        }
+    }
+
+    /// Removes a rule, returning it if it was in the list
+    pub fn remove(&mut self, rule: &str) -> Option<Rule> {
+        let found = self.get(rule)?;
+        let index = self.rules.iter().position(|x| x == found)?;
+        let removed = self.rules.remove(index);
+        // the rules after it moved down
+        self.root.forget(removed.name(), index);
+        // a rule written with A-labels may have hidden a Unicode one
+        let name = removed.name();
+        if name.contains(punycode::PREFIX) {
+            let hidden = self
+                .rules
+                .iter()
+                .position(|rule| to_ascii(rule.name()).as_deref() == Some(name));
+            if let Some(index) = hidden {
+                self.alias(name, index);
+            }
+        }
+        Some(removed)
+    }
+
+    // whether a rule, or the A-label form of one, ends at `name`
+    pub(super) fn ends_at(&self, name: &str) -> bool {
+        let mut node = &self.root;
+        for label in name.rsplit('.') {
+            node = match node.children.get(label.as_bytes()) {
+                Some(node) => node,
+                None => return false,
+            };
+        }
+        node.rule.is_some()
+    }
+
+    /// The rule with the given text, e.g. `*.ck`
+    pub fn get(&self, rule: &str) -> Option<&Rule> {
+        let mut node = &self.root;
+        for label in rule.trim_start_matches(EXCEPTION).rsplit('.') {
+            node = node.children.get(label.as_bytes())?;
+        }
+        let found = &self.rules[node.rule?];
+        if found.as_str() == rule {
+            Some(found)
+        } else {
+            None
+        }
+    }
+
+    /// All the rules, in the order they were added
+    pub fn iter(&self) -> impl Iterator<Item = &Rule> {
+        self.rules.iter()
+    }
+
+    /// The rules under a top level domain, including the TLD itself
+    pub fn under<'a>(&'a self, tld: &'a str) -> impl Iterator<Item = &'a Rule> {
+        let tld = tld.trim_matches('.');
+        self.iter().filter(move |rule| rule.tld() == tld)
+    }
+
+    /// The number of rules
+    pub fn len(&self) -> usize {
+        self.rules.len()
+    }
+
+    /// Whether there are no rules
+    pub fn is_empty(&self) -> bool {
+        self.rules.is_empty()
+    }
+}
+
+impl Versioned for Rules {
+    fn metadata(&self) -> Metadata<'_> {
+        Rules::metadata(self)
+    }
+}
+
+impl List for Rules {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        self.lookup(labels).0
+    }
+}
+
+impl Rules {
+    // also returns the rule that prevailed, if any
+    pub(super) fn lookup<'a, T>(&self, mut labels: T) -> (Info, Option<&Rule>)
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        // the implicit `*` rule makes the last label a suffix
+        let (mut node, mut info, mut prevailing) = match labels.next() {
+            Some(label) => {
+                let info = Info {
+                    len: label.len(),
+                    typ: None,
+                };
+                match self.root.children.get(label) {
+                    Some(node) => {
+                        let rule = node.rule.map(|index| &self.rules[index]);
+                        let typ = rule.map(|rule| rule.typ);
+                        (node, Info { typ, ..info }, rule)
+                    }
+                    None => return (info, None),
+                }
+            }
+            None => return (Info { len: 0, typ: None }, None),
+        };
+
+        let mut len = info.len;
+        for label in labels {
+            node = match node.children.get(label) {
+                Some(node) => node,
+                None => match node.children.get(WILDCARD.as_bytes()) {
+                    Some(node) => node,
+                    None => break,
+                },
+            };
+            let with_label = len + 1 + label.len();
+            if let Some(index) = node.rule {
+                let rule = &self.rules[index];
+                info.typ = Some(rule.typ);
+                prevailing = Some(rule);
+                if rule.is_exception() {
+                    info.len = len;
+                    break;
+                }
+                info.len = with_label;
+            }
+            len = with_label;
+        }
+
+        (info, prevailing)
+    }
+}
+
+/// Parses rules in the format of `public_suffix_list.dat`
+///
+/// Rules before the `===BEGIN ICANN DOMAINS===` and
+/// `===BEGIN PRIVATE DOMAINS===` markers are ICANN rules. Errors refer to
+/// the line of the invalid rule.
+pub fn parse_rules(input: &str) -> crate::Result<'_, Rules> {
+    Rules::parse(input)
+}
+
+// returns the rule without the `!` of an exception
+// the name with its non-ASCII labels written as A-labels, if it has any
+fn to_ascii(name: &str) -> Option<String> {
+    if name.is_ascii() {
+        return None;
+    }
+    let mut ascii = String::new();
+    for (i, label) in name.split('.').enumerate() {
+        if i > 0 {
+            ascii.push('.');
+        }
+        if label.is_ascii() {
+            ascii.push_str(label);
+        } else {
+            ascii.push_str(punycode::PREFIX);
+            punycode::encode(label.chars(), &mut ascii).ok()?;
+        }
+    }
+    Some(ascii)
+}
+
+fn check(rule: &str) -> crate::error::Result<&str> {
+    let name = rule.trim_start_matches(EXCEPTION);
+    if name.len() + 1 < rule.len() {
+        return Err(Kind::InvalidRule.at(0, rule.len() - name.len()));
+    }
+    if name.len() < rule.len() && !name.contains('.') {
+        return Err(Kind::InvalidRule.at(0, rule.len()));
+    }
+    let offset = rule.len() - name.len();
+    let mut start = offset;
+    for (i, label) in name.split('.').enumerate() {
+        if label.is_empty() {
+            return Err(Kind::EmptyLabel.at(start, start).in_label(i));
+        }
+        start += label.len() + 1;
+    }
+    Ok(name)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{is_suffix, LIST};
+    use crate::parser::DomainName;
+
+    #[test]
+    fn enumerate() {
+        let rules = parse_rules(LIST).unwrap();
+        assert_eq!(rules.len(), 7);
+        let all = rules.iter().map(Rule::as_str).collect::<Vec<_>>();
+        assert_eq!(
+            all,
+            [
+                "com",
+                "uk",
+                "co.uk",
+                "*.ck",
+                "!www.ck",
+                "github.io",
+                "blogspot.co.uk"
+            ]
+        );
+        let uk = rules.under(".uk").map(Rule::as_str).collect::<Vec<_>>();
+        assert_eq!(uk, ["uk", "co.uk", "blogspot.co.uk"]);
+        assert_eq!(rules.get("github.io").unwrap().typ(), Type::Private);
+        assert!(rules.get("*.ck").unwrap().is_wildcard());
+        assert!(rules.get("!www.ck").unwrap().is_exception());
+        assert_eq!(rules.get("!www.ck").unwrap().name(), "www.ck");
+        assert!(rules.get("io").is_none());
+    }
+
+    #[test]
+    fn invalid_rules() {
+        let error = parse_rules("com\nco..uk").unwrap_err();
+        assert_eq!(error.kind(), Kind::EmptyLabel);
+        assert_eq!(error.input(), "co..uk");
+        assert_eq!(error.span(), Some(3..3));
+        assert_eq!(parse_rules("!ck").unwrap_err().kind(), Kind::InvalidRule);
+        assert_eq!(
+            parse_rules("!!www.ck").unwrap_err().kind(),
+            Kind::InvalidRule
+        );
+
+        let mut rules = Rules::new();
+        rules.insert("corp.example", Type::Private).unwrap();
+        rules.insert("corp.example", Type::Icann).unwrap();
+        assert_eq!(rules.len(), 1);
+        assert_eq!(rules.get("corp.example").unwrap().typ(), Type::Icann);
+
+        assert_eq!(
+            rules.remove("corp.example").unwrap().as_str(),
+            "corp.example"
+        );
+        assert!(rules.remove("corp.example").is_none());
+        assert!(rules.is_empty());
+    }
+
+    #[test]
+    fn a_labels() {
+        let mut rules = parse_rules("中国\n公司.香港").unwrap();
+        assert_eq!(rules.len(), 2);
+        assert!(is_suffix(&rules, "中国"));
+        assert!(is_suffix(&rules, "xn--fiqs8s"));
+        assert!(is_suffix(&rules, "xn--55qx5d.xn--j6w193g"));
+        let name = rules.parse_domain_name("www.example.xn--fiqs8s").unwrap();
+        assert!(name.has_known_suffix());
+        assert_eq!(name.root(), Some("example.xn--fiqs8s"));
+        assert!(rules.get("xn--fiqs8s").is_none());
+
+        // a rule written as an A-label is kept as its own rule
+        rules.insert("xn--fiqs8s", Type::Private).unwrap();
+        assert_eq!(rules.len(), 3);
+        assert_eq!(rules.get("xn--fiqs8s").unwrap().typ(), Type::Private);
+        assert_eq!(rules.get("中国").unwrap().typ(), Type::Icann);
+
+        rules.remove("公司.香港").unwrap();
+        assert!(!is_suffix(&rules, "xn--55qx5d.xn--j6w193g"));
+        assert!(is_suffix(&rules, "xn--fiqs8s"));
+
+        // removing it brings back the A-label form of `中国`
+        rules.remove("xn--fiqs8s").unwrap();
+        assert!(is_suffix(&rules, "xn--fiqs8s"));
+        let name = rules.parse_domain_name("www.example.xn--fiqs8s").unwrap();
+        assert_eq!(name.suffix(), "xn--fiqs8s");
+        assert!(name.is_icann());
+        assert!(rules.get("xn--fiqs8s").is_none());
+    }
+}
@@ -0,0 +1,113 @@
+use super::{suffix_labels, Metadata, Versioned, MAX_LABELS};
+use psl_types::{Info, List, Type};
+
+/// Only the rules of one section of another list
+///
+/// Rules from the other section are ignored, so the next shorter rule
+/// that matches is used instead. This lets registrar-level logic treat
+/// `github.io` as an ordinary domain under `io` while browser-level logic
+/// keeps honouring it.
+///
+/// ```rust
+/// use addr::list::Section;
+/// use addr::parser::DomainName;
+/// use psl_types::Type;
+///
+/// let icann = Section::new(psl::List, Type::Icann);
+/// let domain = icann.parse_domain_name("example.github.io").unwrap();
+/// assert_eq!(domain.root(), Some("github.io"));
+///
+/// let private = Section::new(psl::List, Type::Private);
+/// let domain = private.parse_domain_name("example.github.io").unwrap();
+/// assert_eq!(domain.root(), Some("example.github.io"));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Section<L> {
+    list: L,
+    typ: Type,
+}
+
+impl<L> Section<L> {
+    /// Keeps only the rules of `list` in section `typ`
+    pub const fn new(list: L, typ: Type) -> Self {
+        Self { list, typ }
+    }
+
+    /// The list the rules are taken from
+    pub const fn list(&self) -> &L {
+        &self.list
+    }
+
+    /// The section that is kept
+    pub const fn typ(&self) -> Type {
+        self.typ
+    }
+}
+
+/// The version of the list the rules are taken from
+impl<L: Versioned> Versioned for Section<L> {
+    fn metadata(&self) -> Metadata<'_> {
+        self.list.metadata()
+    }
+}
+
+impl<L: List> List for Section<L> {
+    fn find<'a, T>(&self, labels: T) -> Info
+    where
+        T: Iterator<Item = &'a [u8]>,
+    {
+        let mut buf: [&[u8]; MAX_LABELS] = [&[]; MAX_LABELS];
+        let mut count = 0;
+        for label in labels.take(MAX_LABELS) {
+            buf[count] = label;
+            count += 1;
+        }
+        if count == 0 {
+            return Info { len: 0, typ: None };
+        }
+        let labels = &buf[..count];
+        loop {
+            let info = self.list.find(labels[..count].iter().copied());
+            match info.typ {
+                Some(typ) if typ != self.typ => {}
+                _ => return info,
+            }
+            count = suffix_labels(labels, info.len) - 1;
+            if count == 0 {
+                return Info {
+                    len: labels[0].len(),
+                    typ: None,
+                };
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::list::{is_suffix, parse_rules, LIST};
+    use crate::parser::DomainName;
+
+    #[test]
+    fn sections() {
+        let icann = Section::new(psl::List, Type::Icann);
+        let private = Section::new(psl::List, Type::Private);
+
+        let name = icann.parse_domain_name("a.b.example.github.io").unwrap();
+        assert_eq!(name.suffix(), "io");
+        let name = private.parse_domain_name("a.b.example.github.io").unwrap();
+        assert_eq!(name.suffix(), "github.io");
+
+        // exceptions and wildcards are kept with their section
+        let name = icann.parse_domain_name("test.www.ck").unwrap();
+        assert_eq!(name.root(), Some("www.ck"));
+        let name = private.parse_domain_name("test.www.ck").unwrap();
+        assert_eq!(name.suffix(), "ck");
+        assert!(!name.has_known_suffix());
+
+        let rules = Section::new(parse_rules(LIST).unwrap(), Type::Private);
+        assert!(is_suffix(&rules, "github.io"));
+        assert!(!is_suffix(&rules, "co.uk"));
+    }
+}
@@ -0,0 +1,350 @@
+//! `mailto:` URIs
+//!
+//! See [RFC 6068](https://tools.ietf.org/html/rfc6068)
+
+use crate::email::Address;
+use crate::error::{Kind, Result};
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use psl_types::List;
+
+const SCHEME: &str = "mailto:";
+
+/// A parsed `mailto:` URI
+///
+/// All the components are stored percent-decoded.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug, Default)]
+pub struct Uri {
+    to: Vec<String>,
+    cc: Vec<String>,
+    bcc: Vec<String>,
+    headers: Vec<(String, String)>,
+}
+
+impl Uri {
+    /// Creates an empty URI
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub(crate) fn parse(input: &str) -> Result<Uri> {
+        let scheme = input.get(..SCHEME.len()).ok_or(Kind::InvalidMailto)?;
+        if !scheme.eq_ignore_ascii_case(SCHEME) {
+            return Err(Kind::InvalidMailto.into());
+        }
+        let rest = &input[SCHEME.len()..];
+        if rest.contains('#') {
+            return Err(Kind::InvalidMailto.into());
+        }
+        let (to, hfields) = match rest.find('?') {
+            Some(index) => (&rest[..index], Some(&rest[index + 1..])),
+            None => (rest, None),
+        };
+
+        let mut uri = Self::new();
+        push_addresses(&mut uri.to, to)?;
+        for hfield in hfields.into_iter().flat_map(|x| x.split('&')) {
+            if hfield.is_empty() {
+                continue;
+            }
+            let mut parts = hfield.splitn(2, '=');
+            let name = decode(parts.next().unwrap_or_default())?;
+            let value = parts.next().ok_or(Kind::InvalidMailto)?;
+            if name.is_empty() {
+                return Err(Kind::InvalidMailto.into());
+            }
+            if name.eq_ignore_ascii_case("to") {
+                push_addresses(&mut uri.to, value)?;
+            } else if name.eq_ignore_ascii_case("cc") {
+                push_addresses(&mut uri.cc, value)?;
+            } else if name.eq_ignore_ascii_case("bcc") {
+                push_addresses(&mut uri.bcc, value)?;
+            } else {
+                uri.headers.push((name, decode(value)?));
+            }
+        }
+
+        Ok(uri)
+    }
+
+    /// The recipients, from both the path and any `to` header fields
+    pub fn to(&self) -> &[String] {
+        &self.to
+    }
+
+    /// The carbon copy recipients
+    pub fn cc(&self) -> &[String] {
+        &self.cc
+    }
+
+    /// The blind carbon copy recipients
+    pub fn bcc(&self) -> &[String] {
+        &self.bcc
+    }
+
+    /// The `subject` header field
+    pub fn subject(&self) -> Option<&str> {
+        self.header("subject")
+    }
+
+    /// The `body` pseudo header field
+    pub fn body(&self) -> Option<&str> {
+        self.header("body")
+    }
+
+    /// The first header field with the given name, ignoring case
+    ///
+    /// Recipients are not header fields; use `to`, `cc` and `bcc` instead.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers().find_map(|(key, value)| {
+            if key.eq_ignore_ascii_case(name) {
+                Some(value)
+            } else {
+                None
+            }
+        })
+    }
+
+    /// All the header fields other than the recipients, in order
+    pub fn headers(&self) -> impl Iterator<Item = (&str, &str)> {
+        self.headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+    }
+
+    /// Parses the recipients as email addresses
+    pub fn to_addresses<'a, T: List + ?Sized>(
+        &'a self,
+        list: &T,
+    ) -> crate::Result<'a, Vec<Address<'a>>> {
+        parse_addresses(list, &self.to)
+    }
+
+    /// Parses the carbon copy recipients as email addresses
+    pub fn cc_addresses<'a, T: List + ?Sized>(
+        &'a self,
+        list: &T,
+    ) -> crate::Result<'a, Vec<Address<'a>>> {
+        parse_addresses(list, &self.cc)
+    }
+
+    /// Parses the blind carbon copy recipients as email addresses
+    pub fn bcc_addresses<'a, T: List + ?Sized>(
+        &'a self,
+        list: &T,
+    ) -> crate::Result<'a, Vec<Address<'a>>> {
+        parse_addresses(list, &self.bcc)
+    }
+
+    /// Adds a recipient
+    pub fn push_to(&mut self, address: &Address<'_>) {
+        self.to.push(address.as_str().to_owned());
+    }
+
+    /// Adds a carbon copy recipient
+    pub fn push_cc(&mut self, address: &Address<'_>) {
+        self.cc.push(address.as_str().to_owned());
+    }
+
+    /// Adds a blind carbon copy recipient
+    pub fn push_bcc(&mut self, address: &Address<'_>) {
+        self.bcc.push(address.as_str().to_owned());
+    }
+
+    /// Adds a header field, like `subject` or `body`
+    ///
+    /// `to`, `cc` and `bcc` add the value as a recipient instead, as that
+    /// is how they are parsed back.
+    pub fn push_header(&mut self, name: &str, value: &str) {
+        let recipients = if name.eq_ignore_ascii_case("to") {
+            &mut self.to
+        } else if name.eq_ignore_ascii_case("cc") {
+            &mut self.cc
+        } else if name.eq_ignore_ascii_case("bcc") {
+            &mut self.bcc
+        } else {
+            self.headers.push((name.to_owned(), value.to_owned()));
+            return;
+        };
+        recipients.push(value.to_owned());
+    }
+}
+
+impl fmt::Display for Uri {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(SCHEME)?;
+        write_addresses(f, &self.to)?;
+        let mut separator = '?';
+        for &(name, addresses) in &[("cc", &self.cc), ("bcc", &self.bcc)] {
+            if !addresses.is_empty() {
+                write!(f, "{}{}=", separator, name)?;
+                write_addresses(f, addresses)?;
+                separator = '&';
+            }
+        }
+        for (name, value) in self.headers() {
+            write!(f, "{}", separator)?;
+            encode(f, name, is_hfield)?;
+            f.write_str("=")?;
+            encode(f, value, is_hfield)?;
+            separator = '&';
+        }
+        Ok(())
+    }
+}
+
+/// Parses a `mailto:` URI
+///
+/// The recipients are only decoded here. Use `Uri::to_addresses` and
+/// friends to validate them.
+pub fn parse_mailto(input: &str) -> crate::Result<'_, Uri> {
+    Uri::parse(input).map_err(|cause| cause.error_with(input))
+}
+
+fn parse_addresses<'a, T: List + ?Sized>(
+    list: &T,
+    addresses: &'a [String],
+) -> crate::Result<'a, Vec<Address<'a>>> {
+    addresses
+        .iter()
+        .map(|address| Address::parse(list, address).map_err(|cause| cause.error_with(address)))
+        .collect()
+}
+
+fn push_addresses(addresses: &mut Vec<String>, input: &str) -> Result<()> {
+    for address in input.split(',').filter(|x| !x.is_empty()) {
+        addresses.push(decode(address)?);
+    }
+    Ok(())
+}
+
+fn decode(input: &str) -> Result<String> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut iter = input.bytes();
+    while let Some(byte) = iter.next() {
+        if byte == b'%' {
+            let high = iter.next().and_then(hex).ok_or(Kind::InvalidMailto)?;
+            let low = iter.next().and_then(hex).ok_or(Kind::InvalidMailto)?;
+            bytes.push(high << 4 | low);
+        } else {
+            bytes.push(byte);
+        }
+    }
+    String::from_utf8(bytes).map_err(|_| Kind::InvalidMailto.into())
+}
+
+fn hex(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|x| x as u8)
+}
+
+fn write_addresses(f: &mut fmt::Formatter, addresses: &[String]) -> fmt::Result {
+    for (index, address) in addresses.iter().enumerate() {
+        if index > 0 {
+            f.write_str(",")?;
+        }
+        // the host of an IDN is percent-encoded as UTF-8 too
+        match address.rfind('@') {
+            Some(at_sign) => {
+                encode(f, &address[..at_sign], is_address)?;
+                f.write_str("@")?;
+                encode(f, &address[at_sign + 1..], is_address)?;
+            }
+            None => encode(f, address, is_address)?,
+        }
+    }
+    Ok(())
+}
+
+fn encode(f: &mut fmt::Formatter, input: &str, keep: fn(u8) -> bool) -> fmt::Result {
+    for byte in input.bytes() {
+        if keep(byte) {
+            write!(f, "{}", byte as char)?;
+        } else {
+            write!(f, "%{:02X}", byte)?;
+        }
+    }
+    Ok(())
+}
+
+// unreserved / some-delims
+fn is_hfield(byte: u8) -> bool {
+    byte.is_ascii_alphanumeric() || b"-._~!$'()*+,;:@".contains(&byte)
+}
+
+// commas separate addresses and the last `@` separates the host
+fn is_address(byte: u8) -> bool {
+    byte != b',' && byte != b'@' && is_hfield(byte)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use alloc::string::ToString;
+    use psl::List;
+
+    #[test]
+    fn parse() {
+        let uri = parse_mailto(
+            "mailto:john%2Bnews@example.com,jane@example.com?cc=bob@example.com&subject=Hello%20there&body=Line%0D%0ATwo",
+        )
+        .unwrap();
+        assert_eq!(uri.to(), ["john+news@example.com", "jane@example.com"]);
+        assert_eq!(uri.cc(), ["bob@example.com"]);
+        assert_eq!(uri.subject(), Some("Hello there"));
+        assert_eq!(uri.body(), Some("Line\r\nTwo"));
+        let to = uri.to_addresses(&List).unwrap();
+        assert_eq!(to[0].user(), "john+news");
+
+        let uri = parse_mailto("MAILTO:?to=%22not%40me%22@example.org").unwrap();
+        assert_eq!(uri.to(), [r#""not@me"@example.org"#]);
+        assert!(uri.to_addresses(&List).is_ok());
+
+        let uri = parse_mailto("mailto:user@%E4%BE%8B%E5%AD%90.%E5%B9%BF%E5%91%8A").unwrap();
+        assert_eq!(uri.to(), ["user@例子.广告"]);
+
+        parse_mailto("http://example.com").unwrap_err();
+        parse_mailto("mailto:user@example.com?subject=%zz").unwrap_err();
+        parse_mailto("mailto:user@example.com?subject").unwrap_err();
+        parse_mailto("mailto:%ff@example.com").unwrap_err();
+        assert!(parse_mailto("mailto:not-an-address")
+            .unwrap()
+            .to_addresses(&List)
+            .is_err());
+    }
+
+    #[test]
+    fn serialize() {
+        let mut uri = Uri::new();
+        let address = Address::parse(&List, r#""john doe"@例子.广告"#).unwrap();
+        uri.push_to(&address);
+        let address = Address::parse(&List, "jane+news@example.com").unwrap();
+        uri.push_cc(&address);
+        uri.push_header("subject", "Hi & bye");
+        let uri = uri.to_string();
+        assert_eq!(
+            uri,
+            "mailto:%22john%20doe%22@%E4%BE%8B%E5%AD%90.%E5%B9%BF%E5%91%8A?cc=jane+news@example.com&subject=Hi%20%26%20bye"
+        );
+        let parsed = parse_mailto(&uri).unwrap();
+        assert_eq!(parsed.to(), [r#""john doe"@例子.广告"#]);
+        assert_eq!(parsed.cc(), ["jane+news@example.com"]);
+        assert_eq!(parsed.subject(), Some("Hi & bye"));
+
+        let mut uri = Uri::new();
+        uri.push_header("To", "john@example.com");
+        uri.push_header("cc", "jane@example.com");
+        uri.push_header("bcc", r#""a,b"@example.com"#);
+        assert_eq!(uri.headers().count(), 0);
+        let uri = uri.to_string();
+        assert_eq!(
+            uri,
+            "mailto:john@example.com?cc=jane@example.com&bcc=%22a%2Cb%22@example.com"
+        );
+        let parsed = parse_mailto(&uri).unwrap();
+        assert_eq!(parsed.to(), ["john@example.com"]);
+        assert_eq!(parsed.cc(), ["jane@example.com"]);
+        assert_eq!(parsed.bcc(), [r#""a,b"@example.com"#]);
+    }
+}
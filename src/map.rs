@@ -0,0 +1,601 @@
+//! Maps and sets keyed by domain names
+//!
+//! Names are stored label by label from the right, like in the DNS, so a
+//! lookup finds every entry covering a name in one walk. Entries either
+//! match a name exactly or cover it and all its subdomains, which is what
+//! block and allow lists need.
+//!
+//! ```rust
+//! use addr::map::{DomainSet, Scope};
+//! use addr::parse_domain_name;
+//!
+//! let mut blocked = DomainSet::new();
+//! blocked.insert_subtree("ads.example.com");
+//! blocked.insert("tracker.example.net");
+//!
+//! let name = parse_domain_name("cdn.ads.example.com")?;
+//! let found = blocked.longest_match(&name).unwrap();
+//! assert_eq!(found.domain(), "ads.example.com");
+//! assert_eq!(found.scope(), Scope::Subtree);
+//!
+//! assert!(blocked.contains(&parse_domain_name("tracker.example.net")?));
+//! assert!(!blocked.contains(&parse_domain_name("www.tracker.example.net")?));
+//! # Ok::<(), addr::error::Error>(())
+//! ```
+
+use crate::domain;
+use alloc::{collections::BTreeMap, format, string::String, vec, vec::Vec};
+use core::fmt;
+use psl_types::List;
+
+/// Which names an entry applies to
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub enum Scope {
+    /// Only the name itself
+    Exact,
+    /// The name and all its subdomains
+    Subtree,
+}
+
+/// A map from domain names to values
+///
+/// Names are compared case-insensitively and a trailing dot is ignored.
+/// A name can have both an exact and a subtree entry.
+#[derive(Clone, Eq, PartialEq, Debug)]
+pub struct DomainMap<V> {
+    root: Node<V>,
+    len: usize,
+}
+
+#[derive(Clone, Eq, PartialEq, Debug)]
+struct Node<V> {
+    // keyed by lowercase labels, which is also DNSSEC canonical order
+    children: BTreeMap<Vec<u8>, Node<V>>,
+    exact: Option<V>,
+    subtree: Option<V>,
+}
+
+impl<V> Default for Node<V> {
+    fn default() -> Self {
+        Self {
+            children: BTreeMap::new(),
+            exact: None,
+            subtree: None,
+        }
+    }
+}
+
+impl<V> Node<V> {
+    fn slot(&mut self, scope: Scope) -> &mut Option<V> {
+        match scope {
+            Scope::Exact => &mut self.exact,
+            Scope::Subtree => &mut self.subtree,
+        }
+    }
+
+    fn is_empty(&self) -> bool {
+        self.children.is_empty() && self.exact.is_none() && self.subtree.is_none()
+    }
+
+    fn remove(&mut self, mut labels: core::str::RSplit<'_, char>, scope: Scope) -> Option<V> {
+        match labels.next() {
+            Some(label) => {
+                let key = label.to_ascii_lowercase().into_bytes();
+                let child = self.children.get_mut(&key)?;
+                let value = child.remove(labels, scope);
+                if child.is_empty() {
+                    self.children.remove(&key);
+                }
+                value
+            }
+            None => self.slot(scope).take(),
+        }
+    }
+}
+
+impl<V> Default for DomainMap<V> {
+    fn default() -> Self {
+        Self {
+            root: Node::default(),
+            len: 0,
+        }
+    }
+}
+
+impl<V> DomainMap<V> {
+    /// Creates an empty map
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Maps exactly `name` to `value`, returning the previous value
+    pub fn insert(&mut self, name: &str, value: V) -> Option<V> {
+        self.insert_with(name, Scope::Exact, value)
+    }
+
+    /// Maps `name` and all its subdomains to `value`, returning the
+    /// previous value
+    pub fn insert_subtree(&mut self, name: &str, value: V) -> Option<V> {
+        self.insert_with(name, Scope::Subtree, value)
+    }
+
+    /// Maps `name` to `value` with the given scope
+    pub fn insert_with(&mut self, name: &str, scope: Scope, value: V) -> Option<V> {
+        let mut node = &mut self.root;
+        for label in labels(name) {
+            let key = label.to_ascii_lowercase().into_bytes();
+            node = node.children.entry(key).or_default();
+        }
+        let previous = node.slot(scope).replace(value);
+        if previous.is_none() {
+            self.len += 1;
+        }
+        previous
+    }
+
+    /// Removes the entry for `name` with the given scope
+    pub fn remove(&mut self, name: &str, scope: Scope) -> Option<V> {
+        let value = self.root.remove(labels(name), scope);
+        if value.is_some() {
+            self.len -= 1;
+        }
+        value
+    }
+
+    /// The value of the entry for `name` with the given scope
+    ///
+    /// Unlike `longest_match`, this does not look at parent domains.
+    pub fn get(&self, name: &str, scope: Scope) -> Option<&V> {
+        let mut node = &self.root;
+        for label in labels(name) {
+            node = with_lowercase(label, |key| node.children.get(key))?;
+        }
+        match scope {
+            Scope::Exact => node.exact.as_ref(),
+            Scope::Subtree => node.subtree.as_ref(),
+        }
+    }
+
+    /// The most specific entry that applies to `name`
+    ///
+    /// An exact entry for the name wins over a subtree entry for it, and
+    /// either wins over the subtree entries of its parents.
+    pub fn longest_match<'n>(&self, name: &domain::Name<'n>) -> Option<Match<'n, '_, V>> {
+        self.find(name.as_str())
+    }
+
+    pub(crate) fn find<'n>(&self, full: &'n str) -> Option<Match<'n, '_, V>> {
+        let trimmed = if full.ends_with('.') {
+            full.get(..full.len() - 1).unwrap_or_default()
+        } else {
+            full
+        };
+        let mut node = &self.root;
+        let mut found = None;
+        let mut start = trimmed.len();
+        for label in trimmed.rsplit('.') {
+            node = match with_lowercase(label, |key| node.children.get(key)) {
+                Some(node) => node,
+                None => return found,
+            };
+            start -= label.len();
+            if let Some(value) = &node.subtree {
+                found = Some(Match {
+                    domain: trimmed.get(start..).unwrap_or_default(),
+                    scope: Scope::Subtree,
+                    value,
+                });
+            }
+            start = start.saturating_sub(1);
+        }
+        match &node.exact {
+            Some(value) => Some(Match {
+                domain: trimmed,
+                scope: Scope::Exact,
+                value,
+            }),
+            None => found,
+        }
+    }
+
+    /// The entries in DNSSEC canonical order
+    ///
+    /// Names are sorted by their labels from the right, as lowercase
+    /// bytes, so a domain comes right before its subdomains. The exact
+    /// entry for a name comes before its subtree entry.
+    pub fn iter(&self) -> Iter<'_, V> {
+        Iter {
+            stack: vec![(String::new(), &self.root)],
+            pending: Vec::new(),
+        }
+    }
+
+    /// The entries grouped by the registrable root of their names
+    ///
+    /// Names that are public suffixes themselves, and so have no root,
+    /// are grouped under their own name. Within a group, entries are in
+    /// the order of `iter`.
+    pub fn group_by_root<L>(&self, list: &L) -> BTreeMap<String, Vec<Entry<'_, V>>>
+    where
+        L: List + ?Sized,
+    {
+        let mut groups = BTreeMap::<String, Vec<Entry<'_, V>>>::new();
+        for entry in self.iter() {
+            let root = match domain::Name::parse(list, &entry.name) {
+                Ok(name) => name.root().map(String::from),
+                Err(_) => None,
+            };
+            let root = root.unwrap_or_else(|| entry.name.clone());
+            groups.entry(root).or_default().push(entry);
+        }
+        groups
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Whether the map has no entries
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+}
+
+// the labels of a name from the right
+fn labels(name: &str) -> core::str::RSplit<'_, char> {
+    let name = if name.ends_with('.') {
+        name.get(..name.len() - 1).unwrap_or_default()
+    } else {
+        name
+    };
+    name.rsplit('.')
+}
+
+fn with_lowercase<R>(label: &str, f: impl FnOnce(&[u8]) -> R) -> R {
+    let mut buffer = [0; 63];
+    match buffer.get_mut(..label.len()) {
+        Some(key) => {
+            key.copy_from_slice(label.as_bytes());
+            key.make_ascii_lowercase();
+            f(key)
+        }
+        None => f(label.to_ascii_lowercase().as_bytes()),
+    }
+}
+
+/// The entry found by `DomainMap::longest_match`
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Match<'n, 'v, V> {
+    domain: &'n str,
+    scope: Scope,
+    value: &'v V,
+}
+
+impl<'n, 'v, V> Match<'n, 'v, V> {
+    /// The part of the name the entry is for, without a trailing dot
+    pub const fn domain(&self) -> &'n str {
+        self.domain
+    }
+
+    /// Whether the entry is for the name itself or one of its parents
+    pub const fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// The value of the entry
+    pub const fn value(&self) -> &'v V {
+        self.value
+    }
+}
+
+/// An entry of a `DomainMap`
+#[derive(Clone, Eq, PartialEq, Hash, Debug)]
+pub struct Entry<'a, V> {
+    name: String,
+    scope: Scope,
+    value: &'a V,
+}
+
+impl<'a, V> Entry<'a, V> {
+    /// The name, in lowercase and without a trailing dot
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The names the entry applies to
+    pub const fn scope(&self) -> Scope {
+        self.scope
+    }
+
+    /// The value of the entry
+    pub const fn value(&self) -> &'a V {
+        self.value
+    }
+}
+
+/// Subtree entries are written with a leading dot, e.g. `.example.com`
+///
+/// A `*` label is a literal label in an exact entry, so `*.example.com`
+/// would be ambiguous.
+impl<V> fmt::Display for Entry<'_, V> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.scope {
+            Scope::Exact => f.write_str(&self.name),
+            Scope::Subtree => write!(f, ".{}", self.name),
+        }
+    }
+}
+
+/// The entries of a `DomainMap` in DNSSEC canonical order
+#[derive(Debug)]
+pub struct Iter<'a, V> {
+    stack: Vec<(String, &'a Node<V>)>,
+    // entries of the node last taken off the stack, in reverse
+    pending: Vec<Entry<'a, V>>,
+}
+
+impl<'a, V> Iterator for Iter<'a, V> {
+    type Item = Entry<'a, V>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entry) = self.pending.pop() {
+                return Some(entry);
+            }
+            let (name, node) = self.stack.pop()?;
+            for (label, child) in node.children.iter().rev() {
+                let label = String::from_utf8_lossy(label);
+                let child_name = if name.is_empty() {
+                    label.into_owned()
+                } else {
+                    format!("{}.{}", label, name)
+                };
+                self.stack.push((child_name, child));
+            }
+            if let Some(value) = &node.subtree {
+                self.pending.push(Entry {
+                    name: name.clone(),
+                    scope: Scope::Subtree,
+                    value,
+                });
+            }
+            if let Some(value) = &node.exact {
+                self.pending.push(Entry {
+                    name,
+                    scope: Scope::Exact,
+                    value,
+                });
+            }
+        }
+    }
+}
+
+impl<'a, V> IntoIterator for &'a DomainMap<V> {
+    type Item = Entry<'a, V>;
+    type IntoIter = Iter<'a, V>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// A set of domain names
+///
+/// This is a `DomainMap` without values.
+#[derive(Clone, Eq, PartialEq, Debug, Default)]
+pub struct DomainSet {
+    map: DomainMap<()>,
+}
+
+impl DomainSet {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds exactly `name`, returning whether it was new
+    pub fn insert(&mut self, name: &str) -> bool {
+        self.map.insert(name, ()).is_none()
+    }
+
+    /// Adds `name` and all its subdomains, returning whether it was new
+    pub fn insert_subtree(&mut self, name: &str) -> bool {
+        self.map.insert_subtree(name, ()).is_none()
+    }
+
+    /// Removes the entry for `name` with the given scope, returning
+    /// whether there was one
+    pub fn remove(&mut self, name: &str, scope: Scope) -> bool {
+        self.map.remove(name, scope).is_some()
+    }
+
+    /// Whether any entry applies to `name`
+    pub fn contains(&self, name: &domain::Name<'_>) -> bool {
+        self.map.longest_match(name).is_some()
+    }
+
+    /// The most specific entry that applies to `name`
+    pub fn longest_match<'n>(&self, name: &domain::Name<'n>) -> Option<Match<'n, '_, ()>> {
+        self.map.longest_match(name)
+    }
+
+    /// The entries in DNSSEC canonical order
+    pub fn iter(&self) -> Iter<'_, ()> {
+        self.map.iter()
+    }
+
+    /// The entries grouped by the registrable root of their names
+    pub fn group_by_root<L>(&self, list: &L) -> BTreeMap<String, Vec<Entry<'_, ()>>>
+    where
+        L: List + ?Sized,
+    {
+        self.map.group_by_root(list)
+    }
+
+    /// The number of entries
+    pub fn len(&self) -> usize {
+        self.map.len()
+    }
+
+    /// Whether the set has no entries
+    pub fn is_empty(&self) -> bool {
+        self.map.is_empty()
+    }
+}
+
+impl<'a> Extend<&'a str> for DomainSet {
+    fn extend<I: IntoIterator<Item = &'a str>>(&mut self, names: I) {
+        for name in names {
+            self.insert(name);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::DomainName;
+    use psl::List;
+
+    fn parse(name: &str) -> domain::Name<'_> {
+        List.parse_domain_name(name).unwrap()
+    }
+
+    #[test]
+    fn longest_match() {
+        let mut map = DomainMap::new();
+        map.insert_subtree("example.com", 1);
+        map.insert_subtree("ads.example.com", 2);
+        map.insert("ads.example.com", 3);
+        map.insert("WWW.Example.org.", 4);
+
+        let found = map.longest_match(&parse("a.b.ads.example.com")).unwrap();
+        assert_eq!(found.domain(), "ads.example.com");
+        assert_eq!(found.scope(), Scope::Subtree);
+        assert_eq!(*found.value(), 2);
+
+        let found = map.longest_match(&parse("Ads.Example.com.")).unwrap();
+        assert_eq!(found.domain(), "Ads.Example.com");
+        assert_eq!(*found.value(), 3);
+
+        let found = map.longest_match(&parse("cdn.example.com")).unwrap();
+        assert_eq!(found.domain(), "example.com");
+        assert_eq!(*found.value(), 1);
+
+        assert_eq!(
+            *map.longest_match(&parse("www.example.org"))
+                .unwrap()
+                .value(),
+            4
+        );
+        assert!(map.longest_match(&parse("cdn.www.example.org")).is_none());
+        assert!(map.longest_match(&parse("example.org")).is_none());
+        assert!(map.longest_match(&parse("com")).is_none());
+
+        assert_eq!(map.get("www.example.org", Scope::Exact), Some(&4));
+        assert_eq!(map.get("ads.example.com", Scope::Subtree), Some(&2));
+        assert_eq!(map.get("cdn.example.com", Scope::Subtree), None);
+    }
+
+    #[test]
+    fn insert_and_remove() {
+        let mut map = DomainMap::new();
+        assert_eq!(map.insert("example.com", 1), None);
+        assert_eq!(map.insert("EXAMPLE.com.", 2), Some(1));
+        assert_eq!(map.insert_subtree("example.com", 3), None);
+        assert_eq!(map.len(), 2);
+
+        assert_eq!(map.remove("example.com", Scope::Exact), Some(2));
+        assert_eq!(map.remove("example.com", Scope::Exact), None);
+        assert_eq!(map.remove("example.com", Scope::Subtree), Some(3));
+        assert!(map.is_empty());
+        assert_eq!(map, DomainMap::new());
+    }
+
+    #[test]
+    fn canonical_order() {
+        // the example from RFC 4034, section 6.1
+        let names = [
+            "example",
+            "a.example",
+            "yljkjljk.a.example",
+            "Z.a.example",
+            "zABC.a.EXAMPLE",
+            "z.example",
+            "\u{1}.z.example",
+            "*.z.example",
+            "\u{c8}.z.example",
+        ];
+        let mut set = DomainSet::new();
+        for name in names.iter().rev() {
+            set.insert(name);
+        }
+        set.insert_subtree("a.example");
+        let found = set
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(
+            found,
+            [
+                "example",
+                "a.example",
+                ".a.example",
+                "yljkjljk.a.example",
+                "z.a.example",
+                "zabc.a.example",
+                "z.example",
+                "\u{1}.z.example",
+                "*.z.example",
+                "\u{c8}.z.example",
+            ]
+        );
+    }
+
+    #[test]
+    fn display() {
+        let mut set = DomainSet::new();
+        set.insert("*.example.com");
+        set.insert_subtree("example.com");
+        let found = set
+            .iter()
+            .map(|entry| entry.to_string())
+            .collect::<Vec<_>>();
+        assert_eq!(found, [".example.com", "*.example.com"]);
+    }
+
+    #[test]
+    fn group_by_root() {
+        let mut set = DomainSet::new();
+        set.extend(vec![
+            "www.example.co.uk",
+            "example.co.uk",
+            "co.uk",
+            "a.b.example.com",
+        ]);
+        set.insert_subtree("example.com");
+        let groups = set.group_by_root(&List);
+        let groups = groups
+            .iter()
+            .map(|(root, entries)| {
+                let names = entries.iter().map(ToString::to_string).collect::<Vec<_>>();
+                (root.as_str(), names)
+            })
+            .collect::<Vec<_>>();
+        assert_eq!(
+            groups,
+            [
+                ("co.uk", vec!["co.uk".to_string()]),
+                (
+                    "example.co.uk",
+                    vec!["example.co.uk".into(), "www.example.co.uk".into()]
+                ),
+                (
+                    "example.com",
+                    vec![".example.com".into(), "a.b.example.com".into()]
+                ),
+            ]
+        );
+    }
+}
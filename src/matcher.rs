@@ -1,7 +1,8 @@
-use crate::error::{Kind, Result};
+use crate::error::{Adjust, Kind, Result, Sink};
+use crate::policy::Policy;
+use crate::punycode;
 
 const MAX_DOMAIN_LEN: usize = 253;
-const MAX_LABELS_COUNT: usize = 127;
 const MAX_LABEL_LEN: usize = 63;
 
 /// Check if a domain has valid syntax
@@ -9,50 +10,112 @@ const MAX_LABEL_LEN: usize = 63;
 // http://blog.sacaluta.com/2011/12/dns-domain-names-253-or-255-bytesoctets.html
 // https://blogs.msdn.microsoft.com/oldnewthing/20120412-00/?p=7873/
 #[inline]
-pub(crate) fn is_domain_name(domain: &str) -> Result<()> {
+pub(crate) fn check_domain_name<S: Sink + ?Sized>(
+    domain: &str,
+    policy: &Policy,
+    sink: &mut S,
+) -> Result<()> {
     // check total lengths
-    if domain.chars().count() > MAX_DOMAIN_LEN {
-        return Err(Kind::NameTooLong);
+    if policy.name_len(domain) > MAX_DOMAIN_LEN {
+        sink.report(Kind::NameTooLong.at(0, domain.len()))?;
     }
 
     let dot_count = domain.matches('.').count();
 
-    if dot_count + 1 > MAX_LABELS_COUNT {
-        return Err(Kind::TooManyLabels);
+    if dot_count + 1 > policy.max_labels {
+        sink.report(Kind::TooManyLabels.at(0, domain.len()))?;
     }
 
+    let mut offset = 0;
     for (i, label) in domain.split('.').enumerate() {
-        is_label(label, i == dot_count)?;
+        let mut sink = Adjust::new(sink, |cause| cause.shift(offset).in_label(i));
+        check_label(label, i == dot_count, policy, &mut sink)?;
+        offset += label.len() + 1;
     }
 
     Ok(())
 }
 
-pub(crate) fn is_label(label: &str, label_is_tld: bool) -> Result<()> {
+pub(crate) fn check_label<S: Sink + ?Sized>(
+    label: &str,
+    label_is_tld: bool,
+    policy: &Policy,
+    sink: &mut S,
+) -> Result<()> {
     if label.is_empty() {
-        return Err(Kind::EmptyLabel);
+        return sink.report(Kind::EmptyLabel.at(0, 0));
     }
 
-    if label.chars().count() > MAX_LABEL_LEN {
-        return Err(Kind::LabelTooLong);
+    if policy.label_len(label) > MAX_LABEL_LEN {
+        sink.report(Kind::LabelTooLong.at(0, label.len()))?;
     }
 
-    if label_is_tld && is_num(label) {
-        return Err(Kind::NumericTld);
+    let is_num = if policy.ipv4_numbers {
+        is_ipv4_num
+    } else {
+        is_num
+    };
+    if label_is_tld && !policy.allow_numeric_tld && is_num(label) {
+        sink.report(Kind::NumericTld.at(0, label.len()))?;
     }
 
-    if label.starts_with(|c: char| c.is_ascii() && !c.is_alphanumeric()) {
-        return Err(Kind::LabelStartNotAlnum);
+    // characters already reported at either end aren't reported again
+    let mut reported_start = false;
+    if let Some(c) = label.chars().next() {
+        if c.is_ascii() && !policy.allows(c) {
+            sink.report(Kind::LabelStartNotAlnum.at(0, c.len_utf8()))?;
+            reported_start = true;
+        }
     }
 
-    if label.ends_with(|c: char| c.is_ascii() && !c.is_alphanumeric()) {
-        return Err(Kind::LabelEndNotAlnum);
+    let mut reported_end = false;
+    if let Some(c) = label.chars().next_back() {
+        if c.is_ascii() && !policy.allows(c) {
+            let start = label.len() - c.len_utf8();
+            sink.report(Kind::LabelEndNotAlnum.at(start, label.len()))?;
+            reported_end = true;
+        }
     }
 
-    if label.contains(|c: char| c != '-' && c.is_ascii() && !c.is_alphanumeric()) {
-        return Err(Kind::IllegalCharacter);
+    for (start, c) in label.char_indices() {
+        let is_reported =
+            (reported_start && start == 0) || (reported_end && start + c.len_utf8() == label.len());
+        if is_reported {
+            continue;
+        }
+        let illegal = if c.is_ascii() {
+            c != '-' && !policy.allows(c)
+        } else {
+            policy.ascii_only
+        };
+        if illegal {
+            sink.report(Kind::IllegalCharacter.at(start, start + c.len_utf8()))?;
+        }
     }
 
+    check_hyphens(label, policy.reject_reserved_labels, sink)?;
+
+    Ok(())
+}
+
+// Labels with hyphens in the 3rd and 4th positions are reserved
+// unless they are valid A-labels
+// https://tools.ietf.org/html/rfc5891#section-4.2.3.1
+fn check_hyphens<S: Sink + ?Sized>(label: &str, reject_reserved: bool, sink: &mut S) -> Result<()> {
+    let mut chars = label.char_indices().skip(2);
+    let start = match (chars.next(), chars.next()) {
+        (Some((start, '-')), Some((_, '-'))) => start,
+        _ => return Ok(()),
+    };
+    let prefix = punycode::PREFIX.as_bytes();
+    let is_prefixed = label.as_bytes()[..prefix.len()].eq_ignore_ascii_case(prefix);
+    if !is_prefixed {
+        if reject_reserved {
+            sink.report(Kind::ReservedLabel.at(start, start + 2))?;
+        }
+    } else if !punycode::is_a_label(&label[punycode::PREFIX.len()..]) {
+        sink.report(Kind::InvalidPunycode.at(0, label.len()))?;
+    }
     Ok(())
 }
 
@@ -60,15 +123,29 @@ pub(crate) fn is_num(label: &str) -> bool {
     label.parse::<f64>().is_ok()
 }
 
+// Whether the label could be read as a number in an IPv4 address, in
+// decimal, octal with a leading zero or hex with a `0x` prefix
+// https://url.spec.whatwg.org/#ends-in-a-number-checker
+fn is_ipv4_num(label: &str) -> bool {
+    let bytes = label.as_bytes();
+    let is_hex = bytes.len() >= 2 && bytes[0] == b'0' && (bytes[1] == b'x' || bytes[1] == b'X');
+    if is_hex {
+        bytes[2..].iter().all(u8::is_ascii_hexdigit)
+    } else {
+        !bytes.is_empty() && bytes.iter().all(u8::is_ascii_digit)
+    }
+}
+
 // https://tools.ietf.org/html/rfc2181#section-11
 #[inline]
-pub(crate) fn is_dns_name(name: &str) -> Result<()> {
+pub(crate) fn check_dns_name<S: Sink + ?Sized>(name: &str, sink: &mut S) -> Result<()> {
     if name.is_empty() {
-        return Err(Kind::EmptyName);
+        return sink.report(Kind::EmptyName.at(0, 0));
     }
 
-    if name.contains("..") {
-        return Err(Kind::EmptyLabel);
+    for (index, _) in name.match_indices("..") {
+        let label = name[..=index].matches('.').count();
+        sink.report(Kind::EmptyLabel.at(index + 1, index + 1).in_label(label))?;
     }
 
     let domain = if name.ends_with('.') {
@@ -79,51 +156,63 @@ pub(crate) fn is_dns_name(name: &str) -> Result<()> {
 
     // check total lengths
     if domain.len() > MAX_DOMAIN_LEN {
-        return Err(Kind::NameTooLong);
+        sink.report(Kind::NameTooLong.at(0, domain.len()))?;
     }
 
-    for label in domain.split('.') {
+    let mut offset = 0;
+    for (i, label) in domain.split('.').enumerate() {
         if label.len() > MAX_LABEL_LEN {
-            return Err(Kind::LabelTooLong);
+            sink.report(
+                Kind::LabelTooLong
+                    .at(offset, offset + label.len())
+                    .in_label(i),
+            )?;
         }
+        offset += label.len() + 1;
     }
 
     Ok(())
 }
 
-pub(crate) fn is_email_local(local: &str) -> Result<()> {
-    let mut chars = local.chars();
+pub(crate) fn check_email_local<S: Sink + ?Sized>(local: &str, sink: &mut S) -> Result<()> {
+    let mut chars = local.char_indices();
 
-    let first = chars.next().ok_or(Kind::NoUserPart)?;
+    let first = match chars.next() {
+        Some((_, first)) => first,
+        None => return sink.report(Kind::NoUserPart.at(0, 0)),
+    };
 
     let last_index = chars.clone().count().max(1) - 1;
 
     if last_index > MAX_LABEL_LEN {
-        return Err(Kind::EmailLocalTooLong);
+        sink.report(Kind::EmailLocalTooLong.at(0, local.len()))?;
     }
 
     if first == '"' {
         // quoted
         if last_index == 0 {
-            return Err(Kind::QuoteUnclosed);
+            return sink.report(Kind::QuoteUnclosed.at(0, 1));
         }
-        for (index, c) in chars.enumerate() {
+        for (index, (offset, c)) in chars.enumerate() {
             if index == last_index {
                 if c != '"' {
-                    return Err(Kind::QuoteUnclosed);
+                    sink.report(Kind::QuoteUnclosed.at(0, 1))?;
                 }
             } else if !is_combined(c) && !is_quoted(c) {
-                return Err(Kind::IllegalCharacter);
+                sink.report(Kind::IllegalCharacter.at(offset, offset + c.len_utf8()))?;
             }
         }
     } else {
         // not quoted
-        if first == ' ' || first == '.' || local.contains("..") {
-            return Err(Kind::IllegalCharacter);
+        if first == ' ' || first == '.' {
+            sink.report(Kind::IllegalCharacter.at(0, 1))?;
+        }
+        for (index, _) in local.match_indices("..") {
+            sink.report(Kind::IllegalCharacter.at(index + 1, index + 2))?;
         }
-        for (index, c) in chars.enumerate() {
+        for (index, (offset, c)) in chars.enumerate() {
             if !is_combined(c) && (index == last_index || c != '.') {
-                return Err(Kind::IllegalCharacter);
+                sink.report(Kind::IllegalCharacter.at(offset, offset + c.len_utf8()))?;
             }
         }
     }
@@ -185,12 +274,72 @@ fn is_combined(c: char) -> bool {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::error::FailFast;
+
+    fn is_domain_name(domain: &str) -> Result<()> {
+        check_domain_name(domain, &Policy::new(), &mut FailFast)
+    }
+
+    fn is_dns_name(name: &str) -> Result<()> {
+        check_dns_name(name, &mut FailFast)
+    }
+
+    fn is_email_local(local: &str) -> Result<()> {
+        check_email_local(local, &mut FailFast)
+    }
 
     #[test]
     fn single_label_domain() {
         assert!(is_domain_name("xn--example").is_ok());
     }
 
+    #[test]
+    fn reserved_labels() {
+        assert!(is_domain_name("xn--85x722f.xn--55qx5d.cn").is_ok());
+        assert!(is_domain_name("XN--BCHER-KVA.de").is_ok());
+        assert!(is_domain_name("ab--cd.com").is_ok());
+        assert!(is_domain_name("r3---sn-4g5e6nz7.googlevideo.com").is_ok());
+        assert_eq!(
+            is_domain_name("www.xn--zzzz.com"),
+            Err(Kind::InvalidPunycode.at(4, 12).in_label(1))
+        );
+        assert!(is_domain_name("a--b.com").is_ok());
+
+        let policy = Policy::new().reject_reserved_labels(true);
+        assert_eq!(
+            check_domain_name("ab--cd.com", &policy, &mut FailFast),
+            Err(Kind::ReservedLabel.at(2, 4).in_label(0))
+        );
+        assert!(check_domain_name("xn--55qx5d.cn", &policy, &mut FailFast).is_ok());
+    }
+
+    #[test]
+    fn numeric_tld() {
+        for tld in &["123", "0177", "0x7f000001", "0X7F", "0x"] {
+            assert!(is_ipv4_num(tld), "{}", tld);
+        }
+        for tld in &["nan", "inf", "Infinity", "1e5", "0x7g", "3com", "x0"] {
+            assert!(!is_ipv4_num(tld), "{}", tld);
+        }
+
+        // domain names keep rejecting anything that parses as a float
+        for name in &["example.1e5", "example.nan", "example.inf"] {
+            assert_eq!(
+                is_domain_name(name),
+                Err(Kind::NumericTld.at(8, name.len()).in_label(1))
+            );
+        }
+        assert!(is_domain_name("example.0x7f").is_ok());
+        assert!(is_domain_name("example.0x").is_ok());
+
+        let policy = Policy::new().ipv4_numbers(true);
+        assert!(check_domain_name("example.nan", &policy, &mut FailFast).is_ok());
+        assert_eq!(
+            check_domain_name("example.0x7f", &policy, &mut FailFast),
+            Err(Kind::NumericTld.at(8, 12).in_label(1))
+        );
+    }
+
     #[test]
     fn plain_domain() {
         assert!(is_domain_name("example.com").is_ok());
@@ -200,4 +349,34 @@ mod tests {
     fn subdomains() {
         assert!(is_domain_name("a.b.c.d.e.f").is_ok());
     }
+
+    #[test]
+    fn error_positions() {
+        let cause = is_domain_name("www.exa_mple.com").unwrap_err();
+        assert_eq!(cause, Kind::IllegalCharacter.at(7, 8).in_label(1));
+
+        let cause = is_domain_name("www.example-.com").unwrap_err();
+        assert_eq!(cause, Kind::LabelEndNotAlnum.at(11, 12).in_label(1));
+
+        let cause = is_dns_name("a..b").unwrap_err();
+        assert_eq!(cause, Kind::EmptyLabel.at(2, 2).in_label(1));
+
+        let cause = is_email_local("jöhn doe").unwrap_err();
+        assert_eq!(cause, Kind::IllegalCharacter.at(5, 6));
+    }
+
+    #[test]
+    fn collect_all() {
+        let mut causes = Vec::new();
+        check_domain_name("_www.exa mple_.com", &Policy::new(), &mut causes).unwrap();
+        // the underscores are only reported once
+        assert_eq!(
+            causes,
+            [
+                Kind::LabelStartNotAlnum.at(0, 1).in_label(0),
+                Kind::LabelEndNotAlnum.at(13, 14).in_label(1),
+                Kind::IllegalCharacter.at(8, 9).in_label(1),
+            ]
+        );
+    }
 }
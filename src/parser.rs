@@ -1,14 +1,35 @@
 //! Parser traits
 
-use crate::{dns, domain, email, Result};
+use crate::error::FailFast;
+#[cfg(feature = "alloc")]
+use crate::error::{self, Cause, Error};
+use crate::policy::Policy;
+use crate::{dns, domain, email, hostname, Result};
+#[cfg(feature = "alloc")]
+use alloc::vec::Vec;
 use psl_types::List;
 
+/// The result of a diagnostic parse; all the errors found in the input
+#[cfg(feature = "alloc")]
+pub type Diagnostics<'a, T> = core::result::Result<T, Vec<Error<'a>>>;
+
 /// Parses a domain using the list
 pub trait DomainName {
     /// This method tries to stick to restrictions usually imposed by domain registries
     /// when registering domain names. If your input can potentially include additional
     /// characters allowed in DNS labels, like underscores, use `parse_dns_name` instead.
     fn parse_domain_name<'a>(&self, name: &'a str) -> Result<'a, domain::Name<'a>>;
+
+    /// Parses a domain name enforcing the rules of the given policy
+    fn parse_domain_name_with<'a>(
+        &self,
+        name: &'a str,
+        policy: &Policy,
+    ) -> Result<'a, domain::Name<'a>>;
+
+    /// Like `parse_domain_name` but runs all the checks and reports every error found
+    #[cfg(feature = "alloc")]
+    fn diagnose_domain_name<'a>(&self, name: &'a str) -> Diagnostics<'a, domain::Name<'a>>;
 }
 
 impl<T> DomainName for T
@@ -16,7 +37,23 @@ where
     T: List,
 {
     fn parse_domain_name<'a>(&self, name: &'a str) -> Result<'a, domain::Name<'a>> {
-        domain::Name::parse(self, name).map_err(|kind| kind.error_with(name))
+        domain::Name::parse(self, name).map_err(|cause| cause.error_with(name))
+    }
+
+    fn parse_domain_name_with<'a>(
+        &self,
+        name: &'a str,
+        policy: &Policy,
+    ) -> Result<'a, domain::Name<'a>> {
+        domain::Name::check(self, name, policy, &mut FailFast)
+            .map_err(|cause| cause.error_with(name))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn diagnose_domain_name<'a>(&self, name: &'a str) -> Diagnostics<'a, domain::Name<'a>> {
+        diagnose(name, |causes| {
+            domain::Name::check(self, name, &Policy::new(), causes)
+        })
     }
 }
 
@@ -26,6 +63,10 @@ pub trait DnsName {
     /// in DNS labels but are not usually allowed by domain registries. For that
     /// use `parse_domain_name` instead.
     fn parse_dns_name<'a>(&self, name: &'a str) -> Result<'a, dns::Name<'a>>;
+
+    /// Like `parse_dns_name` but runs all the checks and reports every error found
+    #[cfg(feature = "alloc")]
+    fn diagnose_dns_name<'a>(&self, name: &'a str) -> Diagnostics<'a, dns::Name<'a>>;
 }
 
 impl<T> DnsName for T
@@ -33,13 +74,49 @@ where
     T: List,
 {
     fn parse_dns_name<'a>(&self, name: &'a str) -> Result<'a, dns::Name<'a>> {
-        dns::Name::parse(self, name).map_err(|kind| kind.error_with(name))
+        dns::Name::parse(self, name).map_err(|cause| cause.error_with(name))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn diagnose_dns_name<'a>(&self, name: &'a str) -> Diagnostics<'a, dns::Name<'a>> {
+        diagnose(name, |causes| dns::Name::check(self, name, causes))
+    }
+}
+
+/// Parses a hostname using the list
+pub trait Hostname {
+    /// This method enforces the RFC 952 and RFC 1123 rules for host names;
+    /// letters, digits and hyphens only, and a TLD that is not a decimal,
+    /// octal or hex number so it can't be confused with an IPv4 address. Unlike `parse_domain_name`
+    /// the suffix doesn't have to be in the list.
+    fn parse_hostname<'a>(&self, name: &'a str) -> Result<'a, hostname::Name<'a>>;
+
+    /// Like `parse_hostname` but runs all the checks and reports every error found
+    #[cfg(feature = "alloc")]
+    fn diagnose_hostname<'a>(&self, name: &'a str) -> Diagnostics<'a, hostname::Name<'a>>;
+}
+
+impl<T> Hostname for T
+where
+    T: List,
+{
+    fn parse_hostname<'a>(&self, name: &'a str) -> Result<'a, hostname::Name<'a>> {
+        hostname::Name::parse(self, name).map_err(|cause| cause.error_with(name))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn diagnose_hostname<'a>(&self, name: &'a str) -> Diagnostics<'a, hostname::Name<'a>> {
+        diagnose(name, |causes| hostname::Name::check(self, name, causes))
     }
 }
 
 /// Parses an email address using the list
 pub trait EmailAddress {
     fn parse_email_address<'a>(&self, name: &'a str) -> Result<'a, email::Address<'a>>;
+
+    /// Like `parse_email_address` but runs all the checks and reports every error found
+    #[cfg(feature = "alloc")]
+    fn diagnose_email_address<'a>(&self, name: &'a str) -> Diagnostics<'a, email::Address<'a>>;
 }
 
 impl<T> EmailAddress for T
@@ -49,6 +126,26 @@ where
     /// By default email addresses with IP address host part are not supported.
     /// If you need support for that, enable the `net` feature.
     fn parse_email_address<'a>(&self, name: &'a str) -> Result<'a, email::Address<'a>> {
-        email::Address::parse(self, name).map_err(|kind| kind.error_with(name))
+        email::Address::parse(self, name).map_err(|cause| cause.error_with(name))
+    }
+
+    #[cfg(feature = "alloc")]
+    fn diagnose_email_address<'a>(&self, name: &'a str) -> Diagnostics<'a, email::Address<'a>> {
+        diagnose(name, |causes| email::Address::check(self, name, causes))
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn diagnose<'a, T, F>(input: &'a str, check: F) -> Diagnostics<'a, T>
+where
+    F: FnOnce(&mut Vec<Cause>) -> error::Result<T>,
+{
+    let mut causes = Vec::new();
+    match check(&mut causes) {
+        Ok(value) if causes.is_empty() => Ok(value),
+        _ => Err(causes
+            .into_iter()
+            .map(|cause| cause.error_with(input))
+            .collect()),
     }
 }
@@ -0,0 +1,159 @@
+//! Validation policies
+
+use crate::punycode;
+
+/// The rules a domain name must follow
+///
+/// `Policy::new()` enforces the same rules as `parse_domain_name`. Use the
+/// setters to state any additional or relaxed rules explicitly, e.g.
+///
+/// ```rust
+/// use addr::policy::Policy;
+///
+/// let policy = Policy::new()
+///     .ascii_only(true)
+///     .allow_underscore(true)
+///     .require_known_suffix(true);
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Policy {
+    pub(crate) ascii_only: bool,
+    pub(crate) allow_underscore: bool,
+    pub(crate) allow_numeric_tld: bool,
+    pub(crate) ipv4_numbers: bool,
+    pub(crate) count_octets: bool,
+    pub(crate) wire_lengths: bool,
+    pub(crate) reject_reserved_labels: bool,
+    pub(crate) max_labels: usize,
+    pub(crate) require_known_suffix: bool,
+    pub(crate) reject_private: bool,
+}
+
+impl Policy {
+    /// The default policy
+    pub const fn new() -> Self {
+        Self {
+            ascii_only: false,
+            allow_underscore: false,
+            allow_numeric_tld: false,
+            ipv4_numbers: false,
+            count_octets: false,
+            wire_lengths: true,
+            reject_reserved_labels: false,
+            max_labels: 127,
+            require_known_suffix: false,
+            reject_private: false,
+        }
+    }
+
+    /// Reject labels containing non-ASCII characters (including U-labels)
+    pub const fn ascii_only(mut self, yes: bool) -> Self {
+        self.ascii_only = yes;
+        self
+    }
+
+    /// Allow underscores anywhere in a label, e.g. `_dmarc.example.com`
+    pub const fn allow_underscore(mut self, yes: bool) -> Self {
+        self.allow_underscore = yes;
+        self
+    }
+
+    /// Allow all-numeric top level domains, e.g. `example.123`
+    pub const fn allow_numeric_tld(mut self, yes: bool) -> Self {
+        self.allow_numeric_tld = yes;
+        self
+    }
+
+    // a TLD is numeric if it could be read as a number in an IPv4 address,
+    // rather than if it parses as a float
+    pub(crate) const fn ipv4_numbers(mut self, yes: bool) -> Self {
+        self.ipv4_numbers = yes;
+        self
+    }
+
+    /// Measure label and name lengths in UTF-8 octets instead of code points
+    pub const fn count_octets(mut self, yes: bool) -> Self {
+        self.count_octets = yes;
+        self
+    }
+
+    /// Measure the lengths of IDN labels after conversion to A-labels (punycode)
+    ///
+    /// This is on by default so that names which would overflow the DNS
+    /// limits on the wire are rejected.
+    pub const fn wire_lengths(mut self, yes: bool) -> Self {
+        self.wire_lengths = yes;
+        self
+    }
+
+    /// Reject labels with hyphens in the 3rd and 4th positions, like `ab--cd`
+    ///
+    /// RFC 5891 reserves these labels, but some are in use, e.g.
+    /// `r3---sn-4g5e6nz7.googlevideo.com`, so they are allowed by default.
+    /// `xn--` labels are always checked to decode to a valid U-label and
+    /// encode back to the same label.
+    pub const fn reject_reserved_labels(mut self, yes: bool) -> Self {
+        self.reject_reserved_labels = yes;
+        self
+    }
+
+    /// The maximum number of labels a name can have
+    pub const fn max_labels(mut self, max: usize) -> Self {
+        self.max_labels = max;
+        self
+    }
+
+    /// Reject names whose suffix is not in the list
+    pub const fn require_known_suffix(mut self, yes: bool) -> Self {
+        self.require_known_suffix = yes;
+        self
+    }
+
+    /// Reject names under a PRIVATE suffix, e.g. `example.github.io`
+    pub const fn reject_private(mut self, yes: bool) -> Self {
+        self.reject_private = yes;
+        self
+    }
+
+    pub(crate) fn len(&self, input: &str) -> usize {
+        if self.count_octets {
+            input.len()
+        } else {
+            input.chars().count()
+        }
+    }
+
+    pub(crate) fn label_len(&self, label: &str) -> usize {
+        let len = self.len(label);
+        if self.wire_lengths {
+            len.max(punycode::ascii_len(label).unwrap_or(core::usize::MAX))
+        } else {
+            len
+        }
+    }
+
+    pub(crate) fn name_len(&self, name: &str) -> usize {
+        let len = self.len(name);
+        if self.wire_lengths && !name.is_ascii() {
+            let dots = name.matches('.').count();
+            let labels = name
+                .split('.')
+                .map(|label| self.label_len(label))
+                .fold(dots, usize::saturating_add);
+            len.max(labels)
+        } else {
+            len
+        }
+    }
+
+    // whether an ASCII character other than a hyphen is allowed in a label
+    pub(crate) fn allows(&self, c: char) -> bool {
+        c.is_ascii_alphanumeric() || (self.allow_underscore && c == '_')
+    }
+}
+
+impl Default for Policy {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,299 @@
+//! Punycode encoding and decoding of IDN labels
+//!
+//! See [RFC 3492](https://tools.ietf.org/html/rfc3492)
+
+use core::fmt::{self, Write};
+
+const BASE: u32 = 36;
+const T_MIN: u32 = 1;
+const T_MAX: u32 = 26;
+const SKEW: u32 = 38;
+const DAMP: u32 = 700;
+const INITIAL_BIAS: u32 = 72;
+const INITIAL_N: u32 = 128;
+const MAX_LABEL_LEN: usize = 63;
+
+/// The prefix of an A-label
+pub(crate) const PREFIX: &str = "xn--";
+
+/// Writes the punycode encoding of `input`, without the `xn--` prefix
+///
+/// Returns an error on overflow.
+pub(crate) fn encode<I, W>(input: I, output: &mut W) -> fmt::Result
+where
+    I: Iterator<Item = char> + Clone,
+    W: Write + ?Sized,
+{
+    let mut input_len = 0u32;
+    let mut basic_len = 0u32;
+    for c in input.clone() {
+        input_len += 1;
+        if c.is_ascii() {
+            output.write_char(c)?;
+            basic_len += 1;
+        }
+    }
+    if basic_len > 0 {
+        output.write_char('-')?;
+    }
+
+    let mut n = INITIAL_N;
+    let mut delta = 0u32;
+    let mut bias = INITIAL_BIAS;
+    let mut handled = basic_len;
+    while handled < input_len {
+        let m = input
+            .clone()
+            .map(|c| c as u32)
+            .filter(|c| *c >= n)
+            .min()
+            .ok_or(fmt::Error)?;
+        delta = (m - n)
+            .checked_mul(handled + 1)
+            .and_then(|x| delta.checked_add(x))
+            .ok_or(fmt::Error)?;
+        n = m;
+        for c in input.clone().map(|c| c as u32) {
+            if c < n {
+                delta = delta.checked_add(1).ok_or(fmt::Error)?;
+            }
+            if c == n {
+                let mut q = delta;
+                let mut k = BASE;
+                loop {
+                    let t = threshold(k, bias);
+                    if q < t {
+                        break;
+                    }
+                    output.write_char(digit(t + (q - t) % (BASE - t)))?;
+                    q = (q - t) / (BASE - t);
+                    k += BASE;
+                }
+                output.write_char(digit(q))?;
+                bias = adapt(delta, handled + 1, handled == basic_len);
+                delta = 0;
+                handled += 1;
+            }
+        }
+        delta += 1;
+        n += 1;
+    }
+
+    Ok(())
+}
+
+/// The length of a label once converted to an A-label
+///
+/// ASCII labels are returned as is. Non-ASCII labels are lowercased before
+/// encoding; no other UTS #46 mapping is applied. Returns `None` if the label
+/// can't be encoded.
+pub(crate) fn ascii_len(label: &str) -> Option<usize> {
+    if label.is_ascii() {
+        return Some(label.len());
+    }
+    let mut counter = Counter(PREFIX.len());
+    let chars = label.chars().flat_map(char::to_lowercase);
+    encode(chars, &mut counter).ok()?;
+    Some(counter.0)
+}
+
+/// Decodes `input`, without the `xn--` prefix, into `output`
+///
+/// Returns the number of characters written or `None` if the input is not
+/// valid punycode or doesn't fit.
+pub(crate) fn decode(input: &str, output: &mut [char]) -> Option<usize> {
+    let (basic, extended) = match input.rfind('-') {
+        Some(index) => (&input[..index], &input[index + 1..]),
+        None => ("", input),
+    };
+    if !basic.is_ascii() {
+        return None;
+    }
+    let mut len = 0;
+    for c in basic.chars() {
+        *output.get_mut(len)? = c;
+        len += 1;
+    }
+
+    let mut n = INITIAL_N;
+    let mut i = 0u32;
+    let mut bias = INITIAL_BIAS;
+    let mut digits = extended.bytes();
+    while digits.len() > 0 {
+        let old_i = i;
+        let mut weight = 1u32;
+        let mut k = BASE;
+        loop {
+            let digit = value(digits.next()?)?;
+            i = digit.checked_mul(weight).and_then(|x| i.checked_add(x))?;
+            let t = threshold(k, bias);
+            if digit < t {
+                break;
+            }
+            weight = weight.checked_mul(BASE - t)?;
+            k += BASE;
+        }
+        let points = len as u32 + 1;
+        bias = adapt(i - old_i, points, old_i == 0);
+        n = n.checked_add(i / points)?;
+        i %= points;
+        let c = core::char::from_u32(n).filter(|c| !c.is_ascii())?;
+        if len == output.len() {
+            return None;
+        }
+        let position = i as usize;
+        output.copy_within(position..len, position + 1);
+        output[position] = c;
+        len += 1;
+        i += 1;
+    }
+
+    Some(len)
+}
+
+/// Whether `input`, without the `xn--` prefix, is the encoding of a U-label
+///
+/// The input must decode to a string with at least one non-ASCII character
+/// and no control characters, and encoding that string again must give back
+/// the input.
+pub(crate) fn is_a_label(input: &str) -> bool {
+    let mut buffer = ['\0'; MAX_LABEL_LEN];
+    let len = match decode(input, &mut buffer) {
+        Some(len) => len,
+        None => return false,
+    };
+    let decoded = &buffer[..len];
+    if decoded.iter().all(char::is_ascii) || decoded.iter().any(|c| c.is_control()) {
+        return false;
+    }
+    let mut expected = Expected(input.as_bytes());
+    encode(decoded.iter().copied(), &mut expected).is_ok() && expected.0.is_empty()
+}
+
+// compares what's written with the expected bytes, ignoring ASCII case
+struct Expected<'a>(&'a [u8]);
+
+impl Write for Expected<'_> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        let bytes = self.0.get(..s.len()).ok_or(fmt::Error)?;
+        if !bytes.eq_ignore_ascii_case(s.as_bytes()) {
+            return Err(fmt::Error);
+        }
+        self.0 = &self.0[s.len()..];
+        Ok(())
+    }
+}
+
+struct Counter(usize);
+
+impl Write for Counter {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.0 += s.len();
+        Ok(())
+    }
+}
+
+fn threshold(k: u32, bias: u32) -> u32 {
+    if k <= bias {
+        T_MIN
+    } else if k >= bias + T_MAX {
+        T_MAX
+    } else {
+        k - bias
+    }
+}
+
+fn adapt(mut delta: u32, num_points: u32, first_time: bool) -> u32 {
+    delta /= if first_time { DAMP } else { 2 };
+    delta += delta / num_points;
+    let mut k = 0;
+    while delta > ((BASE - T_MIN) * T_MAX) / 2 {
+        delta /= BASE - T_MIN;
+        k += BASE;
+    }
+    k + (((BASE - T_MIN + 1) * delta) / (delta + SKEW))
+}
+
+fn value(byte: u8) -> Option<u32> {
+    match byte {
+        b'a'..=b'z' => Some((byte - b'a') as u32),
+        b'A'..=b'Z' => Some((byte - b'A') as u32),
+        b'0'..=b'9' => Some((byte - b'0') as u32 + 26),
+        _ => None,
+    }
+}
+
+fn digit(d: u32) -> char {
+    match d {
+        0..=25 => (b'a' + d as u8) as char,
+        _ => (b'0' + (d - 26) as u8) as char,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct Buffer {
+        bytes: [u8; 64],
+        len: usize,
+    }
+
+    impl Write for Buffer {
+        fn write_str(&mut self, s: &str) -> fmt::Result {
+            let end = self.len + s.len();
+            self.bytes
+                .get_mut(self.len..end)
+                .ok_or(fmt::Error)?
+                .copy_from_slice(s.as_bytes());
+            self.len = end;
+            Ok(())
+        }
+    }
+
+    fn encoded(input: &str) -> Buffer {
+        let mut buffer = Buffer {
+            bytes: [0; 64],
+            len: 0,
+        };
+        encode(input.chars(), &mut buffer).unwrap();
+        buffer
+    }
+
+    #[test]
+    fn rfc_samples() {
+        let samples = [
+            ("他们为什么不说中文", "ihqwcrb4cv8a8dqg056pqjye"),
+            ("bücher", "bcher-kva"),
+            ("münchen", "mnchen-3ya"),
+            ("食狮", "85x722f"),
+            ("ليهمابتكلموشعربي؟", "egbpdaj6bu4bxfgehfvwxn"),
+        ];
+        for (input, expected) in samples.iter() {
+            let buffer = encoded(input);
+            assert_eq!(&buffer.bytes[..buffer.len], expected.as_bytes());
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        assert!(is_a_label("85x722f"));
+        assert!(is_a_label("bcher-kva"));
+        assert!(is_a_label("ihqwcrb4cv8a8dqg056pqjye"));
+        assert!(is_a_label("example"));
+        assert!(!is_a_label("zzzz"));
+        assert!(!is_a_label("a"));
+        assert!(!is_a_label("abc-"));
+        assert!(!is_a_label(""));
+
+        let mut buffer = ['\0'; 63];
+        let len = decode("mnchen-3ya", &mut buffer).unwrap();
+        assert!(buffer[..len].iter().copied().eq("münchen".chars()));
+    }
+
+    #[test]
+    fn lengths() {
+        assert_eq!(ascii_len("example"), Some(7));
+        assert_eq!(ascii_len("Bücher"), Some("xn--bcher-kva".len()));
+    }
+}
@@ -0,0 +1,497 @@
+//! Homograph and mixed-script detection
+//!
+//! Checks the labels of a domain name against the rules of
+//! [UTS #39](https://www.unicode.org/reports/tr39/). A-labels are decoded
+//! and all labels are lowercased before they are checked, so
+//! `xn--pple-43d.com` is treated the same as `аpple.com`.
+//!
+//! ```rust
+//! use addr::parse_domain_name;
+//! use addr::security::{self, RestrictionLevel};
+//!
+//! let domain = parse_domain_name("аpple.com").unwrap(); // Cyrillic а
+//! let label = security::labels(&domain).next().unwrap();
+//! assert!(label.is_mixed_script());
+//! assert!(security::restriction_level(&domain) > RestrictionLevel::HighlyRestrictive);
+//!
+//! let genuine = parse_domain_name("apple.com").unwrap();
+//! assert!(security::is_confusable(&domain, &genuine));
+//! ```
+
+use crate::domain::Name;
+use crate::punycode;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::fmt;
+use unicode_security::{MixedScript, RestrictionLevelDetection};
+
+pub use unicode_script::Script;
+pub use unicode_security::mixed_script::AugmentedScriptSet;
+pub use unicode_security::RestrictionLevel;
+
+const MAX_LABEL_LEN: usize = 63;
+
+/// A label of a domain name, as checked by this module
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Label<'a> {
+    label: &'a str,
+    unicode: String,
+}
+
+impl<'a> Label<'a> {
+    fn new(label: &'a str) -> Self {
+        let mut unicode = String::with_capacity(label.len());
+        match decode(label) {
+            Some((buffer, len)) => unicode.extend(buffer[..len].iter()),
+            None => unicode.push_str(label),
+        }
+        let unicode = unicode.chars().flat_map(char::to_lowercase).collect();
+        Self { label, unicode }
+    }
+
+    /// The label as it appears in the domain name
+    pub const fn as_str(&self) -> &'a str {
+        self.label
+    }
+
+    /// The label as a lowercase U-label
+    pub fn unicode(&self) -> &str {
+        &self.unicode
+    }
+
+    /// The scripts all the characters in the label have in common
+    ///
+    /// This is empty if the label mixes scripts.
+    pub fn scripts(&self) -> AugmentedScriptSet {
+        self.unicode.as_str().resolve_script_set()
+    }
+
+    /// Whether the label mixes characters from more than one script
+    pub fn is_mixed_script(&self) -> bool {
+        !self.unicode.as_str().is_single_script()
+    }
+
+    /// The most restrictive level the label satisfies
+    pub fn restriction_level(&self) -> RestrictionLevel {
+        self.unicode.as_str().detect_restriction_level()
+    }
+
+    /// Whether the label looks entirely like a label in another script
+    ///
+    /// For example, the Cyrillic `аррӏе` looks like the Latin `apple`. A label
+    /// is considered a whole-script confusable when it is written in a single
+    /// script and its skeleton is written in a different one.
+    pub fn is_whole_script_confusable(&self) -> bool {
+        let scripts = self.scripts();
+        if scripts.is_empty() || scripts.is_all() {
+            return false;
+        }
+        let skeleton = self.skeleton();
+        let mut target = skeleton.as_str().resolve_script_set();
+        if target.is_empty() || target.is_all() {
+            return false;
+        }
+        target.intersect_with(scripts);
+        target.is_empty()
+    }
+
+    /// The UTS #39 skeleton of the label
+    pub fn skeleton(&self) -> String {
+        unicode_security::skeleton(&self.unicode).collect()
+    }
+}
+
+/// The labels of a domain name, from left to right
+///
+/// The empty label after the trailing dot of a fully qualified name is
+/// skipped.
+pub fn labels<'a>(name: &Name<'a>) -> impl Iterator<Item = Label<'a>> {
+    split(name.as_str())
+}
+
+/// The labels that don't satisfy the given restriction level
+pub fn restricted_labels<'a>(
+    name: &Name<'a>,
+    level: RestrictionLevel,
+) -> impl Iterator<Item = Label<'a>> {
+    labels(name).filter(move |label| label.restriction_level() > level)
+}
+
+/// The least restrictive level of all the labels in a domain name
+///
+/// Each label is checked on its own, so `例子.com` is still single script.
+pub fn restriction_level(name: &Name<'_>) -> RestrictionLevel {
+    labels(name)
+        .map(|label| label.restriction_level())
+        .max()
+        .unwrap_or(RestrictionLevel::ASCIIOnly)
+}
+
+/// The UTS #39 skeleton of a domain name
+///
+/// The trailing dot of a fully qualified name is dropped.
+pub fn skeleton(name: &Name<'_>) -> String {
+    skeleton_of(name.as_str())
+}
+
+/// Whether two domain names can be mistaken for each other
+///
+/// This is the case when they have the same skeleton. Names that are
+/// equal, ignoring case and encoding, are confusable too.
+pub fn is_confusable(a: &Name<'_>, b: &Name<'_>) -> bool {
+    skeleton(a) == skeleton(b)
+}
+
+/// Characters that IDNA 2003 and IDNA 2008 treat differently
+const DEVIATIONS: [char; 4] = ['ß', 'ς', '\u{200C}', '\u{200D}'];
+
+/// The scripts whose whole-script confusables are shown under each TLD
+///
+/// These are the ccTLDs of countries that use those scripts. Anywhere else,
+/// a Cyrillic label that looks Latin is shown as punycode.
+pub const TLD_SCRIPTS: &[(&str, &[Script])] = &[
+    ("bg", &[Script::Cyrillic]),
+    ("by", &[Script::Cyrillic]),
+    ("cy", &[Script::Greek]),
+    ("gr", &[Script::Greek]),
+    ("kg", &[Script::Cyrillic]),
+    ("kz", &[Script::Cyrillic]),
+    ("mk", &[Script::Cyrillic]),
+    ("mn", &[Script::Cyrillic]),
+    ("rs", &[Script::Cyrillic]),
+    ("ru", &[Script::Cyrillic]),
+    ("su", &[Script::Cyrillic]),
+    ("tj", &[Script::Cyrillic]),
+    ("ua", &[Script::Cyrillic]),
+];
+
+/// Decides when a domain name is shown as Unicode, like browsers do
+///
+/// ```rust
+/// use addr::parse_domain_name;
+/// use addr::security::{display_name, DisplayPolicy, Fallback};
+///
+/// let policy = DisplayPolicy::new().top_domains(&["apple.com"]);
+///
+/// let name = parse_domain_name("xn--mnchen-3ya.de").unwrap();
+/// assert_eq!(display_name(&name, &policy).as_str(), "münchen.de");
+///
+/// let name = parse_domain_name("аpple.com").unwrap(); // Cyrillic а
+/// let display = display_name(&name, &policy);
+/// assert_eq!(display.as_str(), "xn--pple-43d.com");
+/// assert_eq!(display.fallback(), Some(&Fallback::Restricted { label: 0 }));
+/// ```
+#[derive(Copy, Clone, Eq, PartialEq, Hash, Debug)]
+pub struct DisplayPolicy<'a> {
+    level: RestrictionLevel,
+    top_domains: &'a [&'a str],
+    tld_scripts: &'a [(&'a str, &'a [Script])],
+}
+
+impl<'a> DisplayPolicy<'a> {
+    /// Requires labels to be highly restrictive, using `TLD_SCRIPTS`
+    /// and no top domains
+    pub const fn new() -> Self {
+        Self {
+            level: RestrictionLevel::HighlyRestrictive,
+            top_domains: &[],
+            tld_scripts: TLD_SCRIPTS,
+        }
+    }
+
+    /// The least restrictive level a label can have and still be shown
+    pub const fn restriction_level(mut self, level: RestrictionLevel) -> Self {
+        self.level = level;
+        self
+    }
+
+    /// Registrable domains that lookalikes are checked against, e.g.
+    /// `google.com`
+    pub const fn top_domains(mut self, domains: &'a [&'a str]) -> Self {
+        self.top_domains = domains;
+        self
+    }
+
+    /// The scripts whose whole-script confusables are shown under each TLD
+    pub const fn tld_scripts(mut self, tld_scripts: &'a [(&'a str, &'a [Script])]) -> Self {
+        self.tld_scripts = tld_scripts;
+        self
+    }
+
+    fn allows(&self, tld: &Label<'_>, scripts: &AugmentedScriptSet) -> bool {
+        let allowed = self
+            .tld_scripts
+            .iter()
+            .filter(|(name, _)| name.eq_ignore_ascii_case(tld.as_str()))
+            .flat_map(|(_, scripts)| scripts.iter());
+        let tld_scripts = tld.scripts();
+        for script in scripts.base.iter() {
+            if !tld_scripts.is_all() && tld_scripts.base.contains_script(script) {
+                return true;
+            }
+            if allowed.clone().any(|allowed| *allowed == script) {
+                return true;
+            }
+        }
+        false
+    }
+}
+
+impl Default for DisplayPolicy<'_> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Why a domain name is shown as punycode
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum Fallback {
+    /// A label contains `ß`, `ς`, ZWNJ or ZWJ
+    Deviation { label: usize },
+    /// A label doesn't satisfy the restriction level
+    Restricted { label: usize },
+    /// A label looks like a label in another script not allowed for the TLD
+    WholeScriptConfusable { label: usize },
+    /// The registrable domain looks like this top domain
+    LooksLike(String),
+}
+
+impl fmt::Display for Fallback {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Fallback::Deviation { label } => {
+                write!(f, "label {} contains a deviation character", label)
+            }
+            Fallback::Restricted { label } => {
+                write!(
+                    f,
+                    "label {} mixes scripts or characters that are not allowed",
+                    label
+                )
+            }
+            Fallback::WholeScriptConfusable { label } => {
+                write!(f, "label {} looks like a label in another script", label)
+            }
+            Fallback::LooksLike(domain) => write!(f, "looks like '{}'", domain),
+        }
+    }
+}
+
+/// How to display a domain name
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct DisplayName {
+    text: String,
+    fallback: Option<Fallback>,
+}
+
+impl DisplayName {
+    /// The name to display, either in Unicode or in punycode
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    /// Why the name is shown as punycode
+    ///
+    /// This is `None` if it is shown as Unicode, or if it is all ASCII anyway.
+    pub fn fallback(&self) -> Option<&Fallback> {
+        self.fallback.as_ref()
+    }
+}
+
+impl fmt::Display for DisplayName {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+/// Decides whether to display a domain name as Unicode or punycode
+///
+/// The labels are checked for deviation characters, against the
+/// restriction level and for whole-script confusables, in that order. The
+/// registrable domain is then compared with the top domains. Names that
+/// are shown as Unicode are lowercased.
+pub fn display_name(name: &Name<'_>, policy: &DisplayPolicy<'_>) -> DisplayName {
+    let labels = labels(name).collect::<Vec<_>>();
+    let fallback = fallback(name, &labels, policy);
+    let mut text = String::with_capacity(name.as_str().len());
+    for (i, label) in labels.iter().enumerate() {
+        if i > 0 {
+            text.push('.');
+        }
+        if fallback.is_none() {
+            text.push_str(label.unicode());
+        } else if label.as_str().is_ascii() {
+            text.push_str(label.as_str());
+        } else {
+            let start = text.len();
+            text.push_str(punycode::PREFIX);
+            if punycode::encode(label.unicode().chars(), &mut text).is_err() {
+                text.truncate(start);
+                text.push_str(label.as_str());
+            }
+        }
+    }
+    if name.as_str().ends_with('.') {
+        text.push('.');
+    }
+    DisplayName { text, fallback }
+}
+
+fn fallback(name: &Name<'_>, labels: &[Label<'_>], policy: &DisplayPolicy<'_>) -> Option<Fallback> {
+    if labels.iter().all(|label| label.unicode().is_ascii()) {
+        return None;
+    }
+    let tld = labels.last()?;
+    for (i, label) in labels.iter().enumerate() {
+        if label.unicode().contains(&DEVIATIONS[..]) {
+            return Some(Fallback::Deviation { label: i });
+        }
+        if label.restriction_level() > policy.level {
+            return Some(Fallback::Restricted { label: i });
+        }
+        if label.is_whole_script_confusable() && !policy.allows(tld, &label.scripts()) {
+            return Some(Fallback::WholeScriptConfusable { label: i });
+        }
+    }
+    let root = name.root().unwrap_or_else(|| name.as_str());
+    let unicode = unicode_of(root);
+    let skeleton = skeleton_of(root);
+    policy
+        .top_domains
+        .iter()
+        .find(|domain| skeleton_of(domain) == skeleton && unicode_of(domain) != unicode)
+        .map(|domain| Fallback::LooksLike((*domain).into()))
+}
+
+fn split(name: &str) -> impl Iterator<Item = Label<'_>> {
+    name.split('.')
+        .filter(|label| !label.is_empty())
+        .map(Label::new)
+}
+
+pub(crate) fn skeleton_of(name: &str) -> String {
+    join(split(name).map(|label| label.skeleton()))
+}
+
+fn unicode_of(name: &str) -> String {
+    join(split(name).map(|label| label.unicode))
+}
+
+fn join(labels: impl Iterator<Item = String>) -> String {
+    labels.collect::<Vec<_>>().join(".")
+}
+
+// decodes an A-label into a U-label
+fn decode(label: &str) -> Option<([char; MAX_LABEL_LEN], usize)> {
+    let prefix = label.get(..punycode::PREFIX.len())?;
+    if !prefix.eq_ignore_ascii_case(punycode::PREFIX) {
+        return None;
+    }
+    let mut buffer = ['\0'; MAX_LABEL_LEN];
+    let len = punycode::decode(&label[punycode::PREFIX.len()..], &mut buffer)?;
+    Some((buffer, len))
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::DomainName;
+    use alloc::borrow::ToOwned;
+    use psl::List;
+
+    #[test]
+    fn scripts() {
+        let name = List.parse_domain_name("www.аpple.例子.com").unwrap();
+        let labels = labels(&name).collect::<alloc::vec::Vec<_>>();
+        assert_eq!(labels.len(), 4);
+        assert!(!labels[0].is_mixed_script());
+        assert!(labels[1].is_mixed_script());
+        assert!(labels[2].scripts().base.contains_script(Script::Han));
+        assert_eq!(labels[0].restriction_level(), RestrictionLevel::ASCIIOnly);
+        assert_eq!(
+            labels[2].restriction_level(),
+            RestrictionLevel::SingleScript
+        );
+
+        let restricted = restricted_labels(&name, RestrictionLevel::HighlyRestrictive)
+            .map(|label| label.as_str())
+            .collect::<alloc::vec::Vec<_>>();
+        assert_eq!(restricted, ["аpple"]);
+    }
+
+    #[test]
+    fn a_labels() {
+        let name = List.parse_domain_name("xn--pple-43d.com").unwrap();
+        let label = labels(&name).next().unwrap();
+        assert_eq!(label.unicode(), "аpple");
+        assert!(label.is_mixed_script());
+    }
+
+    #[test]
+    fn whole_script_confusables() {
+        let name = List.parse_domain_name("аррӏе.com").unwrap();
+        let label = labels(&name).next().unwrap();
+        assert!(!label.is_mixed_script());
+        assert!(label.is_whole_script_confusable());
+
+        let name = List.parse_domain_name("apple.com").unwrap();
+        assert!(!labels(&name).any(|label| label.is_whole_script_confusable()));
+        let name = List.parse_domain_name("例子.com").unwrap();
+        assert!(!labels(&name).any(|label| label.is_whole_script_confusable()));
+    }
+
+    #[test]
+    fn display() {
+        let policy = DisplayPolicy::new().top_domains(&["pay.ru", "apple.com"]);
+        let display = |input| {
+            let name = List.parse_domain_name(input).unwrap();
+            let display = display_name(&name, &policy);
+            (display.as_str().to_owned(), display.fallback().cloned())
+        };
+
+        assert_eq!(display("xn--mnchen-3ya.de"), ("münchen.de".into(), None));
+        assert_eq!(display("Example.com."), ("example.com.".into(), None));
+        assert_eq!(display("例子.广告"), ("例子.广告".into(), None));
+        assert_eq!(
+            display("www.faß.de"),
+            (
+                "www.xn--fa-hia.de".into(),
+                Some(Fallback::Deviation { label: 1 })
+            )
+        );
+        assert_eq!(
+            display("аррӏе.com").1,
+            Some(Fallback::WholeScriptConfusable { label: 0 })
+        );
+        assert_eq!(display("аррӏе.ru").1, None);
+        assert_eq!(display("аррӏе.рф").1, None);
+        assert_eq!(
+            display("www.рау.ru"),
+            (
+                "www.xn--80a5ak.ru".into(),
+                Some(Fallback::LooksLike("pay.ru".into()))
+            )
+        );
+        assert_eq!(display("pay.ru").1, None);
+    }
+
+    #[test]
+    fn skeletons() {
+        let pairs = [
+            ("аpple.com", "apple.com"),
+            ("xn--pple-43d.com", "apple.com"),
+            ("APPLE.com.", "apple.com"),
+            ("раураl.com", "paypal.com"),
+            ("paypa1.com", "paypal.com"),
+        ];
+        for (spoof, genuine) in pairs.iter() {
+            let spoof = List.parse_domain_name(spoof).unwrap();
+            let genuine = List.parse_domain_name(genuine).unwrap();
+            assert!(is_confusable(&spoof, &genuine), "{}", spoof);
+        }
+        let genuine = List.parse_domain_name("apple.com").unwrap();
+        let other = List.parse_domain_name("apples.com").unwrap();
+        assert!(!is_confusable(&other, &genuine));
+    }
+}
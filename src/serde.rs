@@ -1,7 +1,8 @@
+use crate::error::FailFast;
 #[cfg(feature = "net")]
 use crate::net;
-use crate::parser::{DnsName, DomainName, EmailAddress};
-use crate::{dns, domain, email};
+use crate::parser::{DnsName, DomainName, EmailAddress, Hostname};
+use crate::{dns, domain, email, hostname};
 #[cfg(feature = "net")]
 use no_std_net as upstream;
 #[cfg(feature = "psl")]
@@ -55,6 +56,29 @@ impl<'de> Deserialize<'de> for dns::Name<'de> {
     }
 }
 
+impl Serialize for hostname::Name<'_> {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: Serializer,
+    {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+#[cfg(feature = "psl")]
+impl<'de> Deserialize<'de> for hostname::Name<'de> {
+    fn deserialize<D>(deserializer: D) -> Result<Self, D::Error>
+    where
+        D: Deserializer<'de>,
+    {
+        let input = <&str>::deserialize(deserializer)?;
+        List.parse_hostname(input).map_err(|_| {
+            let invalid = Unexpected::Str(input);
+            Error::invalid_value(invalid, &"a hostname")
+        })
+    }
+}
+
 impl Serialize for email::Address<'_> {
     fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
     where
@@ -97,7 +121,7 @@ impl<'de> Deserialize<'de> for email::Host<'de> {
         D: Deserializer<'de>,
     {
         let input = <&str>::deserialize(deserializer)?;
-        email::Host::parse(&List, input).map_err(|_| {
+        email::Host::check(&List, input, &mut FailFast).map_err(|_| {
             let invalid = Unexpected::Str(input);
             Error::invalid_value(invalid, &"an email host")
         })
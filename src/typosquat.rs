@@ -0,0 +1,648 @@
+//! Typosquat and lookalike detection
+//!
+//! Compares domain names against a list of protected registrable domains,
+//! e.g.
+//!
+//! ```rust
+//! use addr::parse_domain_name;
+//! use addr::typosquat::{Detector, Heuristic};
+//!
+//! let protected = [parse_domain_name("example.com").unwrap()];
+//! let detector = Detector::new(&protected);
+//!
+//! let name = parse_domain_name("www.exmaple.com").unwrap();
+//! let found = detector.check(&name).unwrap();
+//! assert_eq!(found.protected(), "example.com");
+//! assert_eq!(found.heuristic(), Heuristic::Transposition);
+//!
+//! let name = parse_domain_name("mail.example.com").unwrap();
+//! assert!(detector.check(&name).is_none());
+//! ```
+//!
+//! `permutations` does the opposite and generates the lookalikes of a name.
+
+use crate::domain::Name;
+#[cfg(feature = "alloc")]
+use crate::list::{Rule, Rules};
+#[cfg(feature = "alloc")]
+use alloc::{collections::BTreeSet, string::String, vec::Vec};
+#[cfg(feature = "alloc")]
+use core::fmt;
+#[cfg(feature = "alloc")]
+use psl_types::{List, Type};
+
+const MAX_LABEL_LEN: usize = 63;
+
+const VOWELS: &str = "aeiou";
+
+// characters that look like other characters or sequences
+#[cfg(feature = "alloc")]
+const HOMOGLYPHS: &[(char, &str)] = &[
+    ('a', "а"),
+    ('c', "с"),
+    ('d', "cl"),
+    ('e', "е"),
+    ('i', "l"),
+    ('l', "1"),
+    ('l', "i"),
+    ('m', "rn"),
+    ('o', "0"),
+    ('o', "о"),
+    ('p', "р"),
+    ('w', "vv"),
+    ('x', "х"),
+    ('y', "у"),
+];
+
+const KEYBOARD: [&str; 4] = ["1234567890", "qwertyuiop", "asdfghjkl", "zxcvbnm"];
+
+/// The heuristic that matched a name to a protected domain
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+#[non_exhaustive]
+pub enum Heuristic {
+    /// The same label under another suffix, e.g. `example.co`
+    SuffixSwap,
+    /// A character was left out, e.g. `exmple.com`
+    Omission,
+    /// A character was repeated, e.g. `exaample.com`
+    Repetition,
+    /// A character was added, e.g. `examplez.com`
+    Addition,
+    /// A hyphen was added, e.g. `ex-ample.com`
+    Hyphenation,
+    /// Two adjacent characters were swapped, e.g. `exmaple.com`
+    Transposition,
+    /// A vowel was replaced by another vowel, e.g. `exomple.com`
+    VowelSwap,
+    /// A character was replaced by a neighbouring key, e.g. `exsmple.com`
+    KeyboardAdjacency,
+    /// A character differs by a single bit, e.g. `eyample.com`
+    Bitsquatting,
+    /// The labels look alike, e.g. `examp1e.com` or `exarnple.com`
+    Homoglyph,
+    /// The labels are within the maximum edit distance
+    EditDistance(usize),
+}
+
+/// A name that looks like a protected domain
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Match<'a> {
+    protected: Name<'a>,
+    heuristic: Heuristic,
+}
+
+impl<'a> Match<'a> {
+    /// The protected domain that was matched
+    pub const fn protected(&self) -> Name<'a> {
+        self.protected
+    }
+
+    /// The heuristic that matched
+    pub const fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+}
+
+/// Checks names against protected registrable domains
+///
+/// Only the registrable part of a name is compared, so subdomains of a
+/// protected domain never match it.
+#[derive(Copy, Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Detector<'a> {
+    protected: &'a [Name<'a>],
+    max_distance: usize,
+}
+
+impl<'a> Detector<'a> {
+    /// Creates a detector with a maximum edit distance of 1
+    pub const fn new(protected: &'a [Name<'a>]) -> Self {
+        Self {
+            protected,
+            max_distance: 1,
+        }
+    }
+
+    /// The maximum Damerau-Levenshtein distance between two labels
+    ///
+    /// Labels that are further apart can still match the other heuristics.
+    pub const fn max_distance(mut self, max: usize) -> Self {
+        self.max_distance = max;
+        self
+    }
+
+    /// The first protected domain the name looks like
+    pub fn check(&self, name: &Name<'_>) -> Option<Match<'a>> {
+        self.matches(name).next()
+    }
+
+    /// All the protected domains the name looks like, in order
+    pub fn matches<'b>(&'b self, name: &'b Name<'_>) -> impl Iterator<Item = Match<'a>> + 'b {
+        self.protected.iter().filter_map(move |protected| {
+            let heuristic = compare(name, protected, self.max_distance)?;
+            Some(Match {
+                protected: *protected,
+                heuristic,
+            })
+        })
+    }
+}
+
+fn compare(name: &Name<'_>, protected: &Name<'_>, max_distance: usize) -> Option<Heuristic> {
+    let (label, suffix) = split(name)?;
+    let (protected_label, protected_suffix) = split(protected)?;
+
+    if label.eq_ignore_ascii_case(protected_label) {
+        if suffix.eq_ignore_ascii_case(protected_suffix) {
+            return None;
+        }
+        return Some(Heuristic::SuffixSwap);
+    }
+
+    #[cfg(feature = "security")]
+    {
+        use crate::security::skeleton_of;
+        let is_idn = !label.is_ascii() || !protected_label.is_ascii() || label.contains("--");
+        if is_idn && skeleton_of(label) == skeleton_of(protected_label) {
+            return Some(Heuristic::Homoglyph);
+        }
+    }
+
+    let mut buffer = ['\0'; MAX_LABEL_LEN];
+    let a = lowercase(label, &mut buffer)?;
+    let mut buffer = ['\0'; MAX_LABEL_LEN];
+    let b = lowercase(protected_label, &mut buffer)?;
+
+    if let Some(heuristic) = single_edit(a, b) {
+        return Some(heuristic);
+    }
+
+    if fold(a).eq(fold(b)) {
+        return Some(Heuristic::Homoglyph);
+    }
+
+    let distance = distance(a, b);
+    if distance <= max_distance {
+        return Some(Heuristic::EditDistance(distance));
+    }
+
+    None
+}
+
+/// A lookalike of a domain name
+#[cfg(feature = "alloc")]
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Hash, Debug)]
+pub struct Candidate {
+    domain: String,
+    heuristic: Heuristic,
+}
+
+#[cfg(feature = "alloc")]
+impl Candidate {
+    /// The registrable domain
+    pub fn as_str(&self) -> &str {
+        &self.domain
+    }
+
+    /// The typo that produced this candidate
+    pub const fn heuristic(&self) -> Heuristic {
+        self.heuristic
+    }
+}
+
+#[cfg(feature = "alloc")]
+impl fmt::Display for Candidate {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.domain)
+    }
+}
+
+/// Generates lookalikes of the registrable part of a name
+///
+/// Only candidates that parse as domain names with the same rules, and
+/// that are registrable domains themselves, are returned. Suffixes are
+/// swapped with the ICANN suffixes in `rules`, leaving out wildcard and
+/// exception rules.
+#[cfg(feature = "alloc")]
+pub fn permutations(rules: &Rules, name: &Name<'_>) -> Vec<Candidate> {
+    let suffixes = rules
+        .iter()
+        .filter(|rule| rule.typ() == Type::Icann && !rule.is_wildcard() && !rule.is_exception())
+        .map(Rule::name);
+    permutations_with(rules, name, suffixes)
+}
+
+/// Like `permutations` but with any list, swapping in the given suffixes
+///
+/// The compiled `psl::List` can't list its rules, so this is how it is
+/// used. Only the suffixes the list knows as ICANN suffixes are swapped
+/// in.
+#[cfg(feature = "alloc")]
+pub fn permutations_with<'s, T, I>(list: &T, name: &Name<'_>, suffixes: I) -> Vec<Candidate>
+where
+    T: List + ?Sized,
+    I: IntoIterator<Item = &'s str>,
+{
+    let (label, suffix) = match split(name) {
+        Some(parts) => parts,
+        None => return Vec::new(),
+    };
+    let label = label
+        .chars()
+        .flat_map(char::to_lowercase)
+        .collect::<Vec<_>>();
+    let mut candidates = Candidates::new(list, &label, suffix);
+    let len = label.len();
+
+    for c in (b'a'..=b'z').chain(b'0'..=b'9') {
+        candidates.push(Heuristic::Addition, len, len, &[c as char]);
+    }
+    for i in 0..len {
+        candidates.push(Heuristic::Omission, i, i + 1, &[]);
+    }
+    for (i, c) in label.iter().enumerate() {
+        candidates.push(Heuristic::Repetition, i, i, &[*c]);
+    }
+    for i in 1..len {
+        candidates.push(
+            Heuristic::Transposition,
+            i - 1,
+            i + 1,
+            &[label[i], label[i - 1]],
+        );
+    }
+    for i in 1..len {
+        candidates.push(Heuristic::Hyphenation, i, i, &['-']);
+    }
+    for (i, c) in label.iter().enumerate() {
+        for vowel in VOWELS.chars() {
+            if VOWELS.contains(*c) {
+                candidates.push(Heuristic::VowelSwap, i, i + 1, &[vowel]);
+            }
+        }
+    }
+    for (i, c) in label.iter().enumerate() {
+        for key in KEYBOARD.iter().flat_map(|keys| keys.chars()) {
+            if is_adjacent(*c, key) {
+                candidates.push(Heuristic::KeyboardAdjacency, i, i + 1, &[key]);
+            }
+        }
+    }
+    for (i, c) in label.iter().enumerate() {
+        for bit in 0..7 {
+            let flipped = (*c as u32 ^ 1 << bit) as u8 as char;
+            if c.is_ascii() && (flipped.is_ascii_lowercase() || flipped.is_ascii_digit()) {
+                candidates.push(Heuristic::Bitsquatting, i, i + 1, &[flipped]);
+            }
+        }
+    }
+    for (i, c) in label.iter().enumerate() {
+        for (_, glyph) in HOMOGLYPHS.iter().filter(|(x, _)| x == c) {
+            let glyph = glyph.chars().collect::<Vec<_>>();
+            candidates.push(Heuristic::Homoglyph, i, i + 1, &glyph);
+        }
+    }
+    for other in suffixes {
+        if !other.eq_ignore_ascii_case(suffix) && is_icann(list, other) {
+            candidates.push_with_suffix(Heuristic::SuffixSwap, other);
+        }
+    }
+
+    candidates.found
+}
+
+#[cfg(feature = "alloc")]
+struct Candidates<'a, T: ?Sized> {
+    list: &'a T,
+    label: &'a [char],
+    suffix: &'a str,
+    seen: BTreeSet<String>,
+    found: Vec<Candidate>,
+}
+
+#[cfg(feature = "alloc")]
+impl<'a, T: List + ?Sized> Candidates<'a, T> {
+    fn new(list: &'a T, label: &'a [char], suffix: &'a str) -> Self {
+        let mut seen = BTreeSet::new();
+        seen.insert(Self::domain(label.iter().copied(), suffix));
+        Self {
+            list,
+            label,
+            suffix,
+            seen,
+            found: Vec::new(),
+        }
+    }
+
+    // replaces `label[start..end]` with `with`
+    fn push(&mut self, heuristic: Heuristic, start: usize, end: usize, with: &[char]) {
+        let label = self.label[..start]
+            .iter()
+            .chain(with)
+            .chain(&self.label[end..])
+            .copied();
+        let domain = Self::domain(label, self.suffix);
+        self.insert(heuristic, domain);
+    }
+
+    fn push_with_suffix(&mut self, heuristic: Heuristic, suffix: &str) {
+        let domain = Self::domain(self.label.iter().copied(), suffix);
+        self.insert(heuristic, domain);
+    }
+
+    fn domain(label: impl Iterator<Item = char>, suffix: &str) -> String {
+        let mut domain = label.collect::<String>();
+        domain.push('.');
+        domain.push_str(suffix);
+        domain
+    }
+
+    fn insert(&mut self, heuristic: Heuristic, domain: String) {
+        if self.seen.contains(&domain) {
+            return;
+        }
+        let is_registrable = match Name::parse(self.list, &domain) {
+            Ok(name) => name.root() == Some(domain.as_str()),
+            Err(_) => false,
+        };
+        if is_registrable {
+            self.found.push(Candidate {
+                domain: domain.clone(),
+                heuristic,
+            });
+        }
+        self.seen.insert(domain);
+    }
+}
+
+#[cfg(feature = "alloc")]
+fn is_icann<T: List + ?Sized>(list: &T, suffix: &str) -> bool {
+    match list.suffix(suffix.as_bytes()) {
+        Some(found) => {
+            found.as_bytes() == suffix.as_bytes()
+                && found.is_known()
+                && found.typ() == Some(Type::Icann)
+        }
+        None => false,
+    }
+}
+
+// the label left of the suffix and the suffix, without a trailing dot
+fn split<'a>(name: &Name<'a>) -> Option<(&'a str, &'a str)> {
+    let root = name.root()?;
+    let suffix = name.suffix();
+    let label = root.get(..root.len() - suffix.len() - 1)?;
+    let suffix = if suffix.ends_with('.') {
+        suffix.get(..suffix.len() - 1).unwrap_or_default()
+    } else {
+        suffix
+    };
+    Some((label, suffix))
+}
+
+fn lowercase<'a>(label: &str, buffer: &'a mut [char; MAX_LABEL_LEN]) -> Option<&'a [char]> {
+    let mut len = 0;
+    for c in label.chars().flat_map(char::to_lowercase) {
+        *buffer.get_mut(len)? = c;
+        len += 1;
+    }
+    Some(&buffer[..len])
+}
+
+// the typos that are a single edit away
+fn single_edit(a: &[char], b: &[char]) -> Option<Heuristic> {
+    let i = a.iter().zip(b).take_while(|(x, y)| x == y).count();
+    if a.len() + 1 == b.len() && a[i..] == b[i + 1..] {
+        return Some(Heuristic::Omission);
+    }
+    if a.len() == b.len() + 1 && a[i + 1..] == b[i..] {
+        let repeated = (i > 0 && a[i] == a[i - 1]) || a.get(i + 1) == Some(&a[i]);
+        if repeated {
+            return Some(Heuristic::Repetition);
+        }
+        if a[i] == '-' {
+            return Some(Heuristic::Hyphenation);
+        }
+        return Some(Heuristic::Addition);
+    }
+    if a.len() != b.len() || i == a.len() {
+        return None;
+    }
+    if a[i + 1..] == b[i + 1..] {
+        if VOWELS.contains(a[i]) && VOWELS.contains(b[i]) {
+            return Some(Heuristic::VowelSwap);
+        }
+        if is_adjacent(a[i], b[i]) {
+            return Some(Heuristic::KeyboardAdjacency);
+        }
+        if (a[i] as u32 ^ b[i] as u32).is_power_of_two() {
+            return Some(Heuristic::Bitsquatting);
+        }
+    }
+    if i + 1 < a.len() && a[i] == b[i + 1] && a[i + 1] == b[i] && a[i + 2..] == b[i + 2..] {
+        return Some(Heuristic::Transposition);
+    }
+    None
+}
+
+// whether two keys are next to each other on a QWERTY keyboard
+fn is_adjacent(a: char, b: char) -> bool {
+    let (a, b) = match (key(a), key(b)) {
+        (Some(a), Some(b)) => (a, b),
+        _ => return false,
+    };
+    let (row, col) = (a.0 as isize, a.1 as isize);
+    let (other_row, other_col) = (b.0 as isize, b.1 as isize);
+    // each row is shifted half a key to the right of the one above
+    match other_row - row {
+        0 => (other_col - col).abs() == 1,
+        -1 => other_col == col || other_col == col + 1,
+        1 => other_col == col || other_col == col - 1,
+        _ => false,
+    }
+}
+
+fn key(c: char) -> Option<(usize, usize)> {
+    KEYBOARD
+        .iter()
+        .enumerate()
+        .find_map(|(row, keys)| Some((row, keys.chars().position(|x| x == c)?)))
+}
+
+// maps characters that look alike to the same character
+fn fold(label: &[char]) -> impl Iterator<Item = char> + '_ {
+    let mut i = 0;
+    core::iter::from_fn(move || {
+        let c = *label.get(i)?;
+        let (folded, len) = match (c, label.get(i + 1)) {
+            ('r', Some('n')) => ('m', 2),
+            ('v', Some('v')) => ('w', 2),
+            ('c', Some('l')) => ('d', 2),
+            ('0', _) | ('о', _) => ('o', 1),
+            ('1', _) | ('i', _) => ('l', 1),
+            ('а', _) => ('a', 1),
+            ('с', _) => ('c', 1),
+            ('е', _) => ('e', 1),
+            ('р', _) => ('p', 1),
+            ('х', _) => ('x', 1),
+            ('у', _) => ('y', 1),
+            (c, _) => (c, 1),
+        };
+        i += len;
+        Some(folded)
+    })
+}
+
+// the optimal string alignment distance
+fn distance(a: &[char], b: &[char]) -> usize {
+    let mut before = [0; MAX_LABEL_LEN + 1];
+    let mut previous = [0; MAX_LABEL_LEN + 1];
+    let mut current = [0; MAX_LABEL_LEN + 1];
+    for (j, cell) in previous.iter_mut().enumerate().take(b.len() + 1) {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = (a[i - 1] != b[j - 1]) as usize;
+            let mut value = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                value = value.min(before[j - 2] + 1);
+            }
+            current[j] = value;
+        }
+        before = previous;
+        previous = current;
+    }
+    previous[b.len()]
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::parser::DomainName;
+    use psl::List;
+
+    fn check(input: &str) -> Option<Heuristic> {
+        let protected = [
+            List.parse_domain_name("example.com").unwrap(),
+            List.parse_domain_name("paypal.co.uk").unwrap(),
+        ];
+        let name = List.parse_domain_name(input).unwrap();
+        let found = Detector::new(&protected).check(&name);
+        found.map(|found| found.heuristic())
+    }
+
+    #[test]
+    fn heuristics() {
+        assert_eq!(check("example.co"), Some(Heuristic::SuffixSwap));
+        assert_eq!(check("www.paypal.com"), Some(Heuristic::SuffixSwap));
+        assert_eq!(check("exmple.com"), Some(Heuristic::Omission));
+        assert_eq!(check("exampple.com"), Some(Heuristic::Repetition));
+        assert_eq!(check("exmaple.com"), Some(Heuristic::Transposition));
+        assert_eq!(check("exsmple.com"), Some(Heuristic::KeyboardAdjacency));
+        assert_eq!(check("eyample.com"), Some(Heuristic::Bitsquatting));
+        assert_eq!(check("examp1e.com"), Some(Heuristic::Homoglyph));
+        assert_eq!(check("exarnple.net"), Some(Heuristic::Homoglyph));
+        assert_eq!(check("еxample.com"), Some(Heuristic::Homoglyph));
+        assert_eq!(check("pay-pal.co.uk"), Some(Heuristic::Hyphenation));
+        assert_eq!(check("exampleq.com"), Some(Heuristic::Addition));
+        assert_eq!(check("exomple.com"), Some(Heuristic::VowelSwap));
+        assert_eq!(check("exampel.com"), Some(Heuristic::Transposition));
+        assert_eq!(check("exapmle.org"), Some(Heuristic::Transposition));
+        assert_eq!(check("exampze.com"), Some(Heuristic::EditDistance(1)));
+    }
+
+    #[test]
+    fn no_match() {
+        assert_eq!(check("example.com"), None);
+        assert_eq!(check("www.EXAMPLE.com."), None);
+        assert_eq!(check("paypal.co.uk"), None);
+        assert_eq!(check("something.com"), None);
+        assert_eq!(check("com"), None);
+    }
+
+    #[test]
+    fn distances() {
+        let distance = |a: &str, b: &str| {
+            let a = a.chars().collect::<alloc::vec::Vec<_>>();
+            let b = b.chars().collect::<alloc::vec::Vec<_>>();
+            distance(&a, &b)
+        };
+        assert_eq!(distance("example", "example"), 0);
+        assert_eq!(distance("example", "exmaple"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("еxample", "example"), 1);
+
+        let protected = [List.parse_domain_name("example.com").unwrap()];
+        let name = List.parse_domain_name("exampel1.com").unwrap();
+        assert!(Detector::new(&protected).check(&name).is_none());
+        let found = Detector::new(&protected).max_distance(2).check(&name);
+        assert_eq!(
+            found.map(|found| found.heuristic()),
+            Some(Heuristic::EditDistance(2))
+        );
+    }
+
+    #[test]
+    fn generate() {
+        let rules =
+            crate::list::parse_rules(include_str!("../tests/public_suffix_list.dat")).unwrap();
+        let name = rules.parse_domain_name("www.example.co.uk").unwrap();
+        let candidates = permutations(&rules, &name);
+        let find = |domain: &str| {
+            candidates
+                .iter()
+                .find(|candidate| candidate.as_str() == domain)
+                .map(|candidate| candidate.heuristic())
+        };
+        assert_eq!(find("examplez.co.uk"), Some(Heuristic::Addition));
+        assert_eq!(find("exmple.co.uk"), Some(Heuristic::Omission));
+        assert_eq!(find("exaample.co.uk"), Some(Heuristic::Repetition));
+        assert_eq!(find("exmaple.co.uk"), Some(Heuristic::Transposition));
+        assert_eq!(find("ex-ample.co.uk"), Some(Heuristic::Hyphenation));
+        assert_eq!(find("exomple.co.uk"), Some(Heuristic::VowelSwap));
+        assert_eq!(find("exsmple.co.uk"), Some(Heuristic::KeyboardAdjacency));
+        assert_eq!(find("eyample.co.uk"), Some(Heuristic::Bitsquatting));
+        assert_eq!(find("exarnple.co.uk"), Some(Heuristic::Homoglyph));
+        assert_eq!(find("еxample.co.uk"), Some(Heuristic::Homoglyph));
+        assert_eq!(find("example.com"), Some(Heuristic::SuffixSwap));
+        assert_eq!(find("example.co.nz"), Some(Heuristic::SuffixSwap));
+        assert_eq!(find("example.co.uk"), None);
+        assert_eq!(find("example.github.io"), None);
+        assert_eq!(find("-example.co.uk"), None);
+        assert_eq!(find("example-.co.uk"), None);
+
+        let protected = [name];
+        let detector = Detector::new(&protected);
+        for candidate in &candidates {
+            let parsed = rules.parse_domain_name(candidate.as_str()).unwrap();
+            assert_eq!(parsed.root(), Some(candidate.as_str()));
+            assert!(detector.check(&parsed).is_some(), "{}", candidate);
+        }
+
+        let name = rules.parse_domain_name("co.uk").unwrap();
+        assert!(permutations(&rules, &name).is_empty());
+    }
+
+    #[test]
+    fn generate_with() {
+        let name = List.parse_domain_name("example.com").unwrap();
+        let suffixes = ["com", "net", "github.io", "notasuffix"];
+        let candidates = permutations_with(&List, &name, suffixes.iter().copied());
+        let swapped = candidates
+            .iter()
+            .filter(|candidate| candidate.heuristic() == Heuristic::SuffixSwap)
+            .map(Candidate::as_str)
+            .collect::<Vec<_>>();
+        assert_eq!(swapped, ["example.net"]);
+    }
+
+    #[cfg(feature = "security")]
+    #[test]
+    fn unicode_homoglyphs() {
+        assert_eq!(check("xn--xample-2of.com"), Some(Heuristic::Homoglyph));
+        assert_eq!(check("ехаmple.com"), Some(Heuristic::Homoglyph));
+    }
+}
@@ -0,0 +1,26 @@
+// This file is @generated by `addr::build`. Do not edit.
+
+/// The compiled public suffix list
+pub static LIST: ::addr::list::Compiled<'static> = ::addr::list::Compiled::new(
+    &[
+        ::addr::list::Entry::branch(b"", 1, 7),
+        ::addr::list::Entry::branch(b"ck", 8, 2),
+        ::addr::list::Entry::icann(b"cn", 10, 2),
+        ::addr::list::Entry::icann(b"com", 12, 1),
+        ::addr::list::Entry::branch(b"io", 13, 1),
+        ::addr::list::Entry::icann(b"uk", 14, 1),
+        ::addr::list::Entry::icann(b"xn--fiqs8s", 15, 0),
+        ::addr::list::Entry::icann(b"\xe4\xb8\xad\xe5\x9b\xbd", 15, 0),
+        ::addr::list::Entry::icann(b"*", 15, 0),
+        ::addr::list::Entry::icann(b"www", 15, 0).exception(),
+        ::addr::list::Entry::icann(b"xn--55qx5d", 15, 0),
+        ::addr::list::Entry::icann(b"\xe5\x85\xac\xe5\x8f\xb8", 15, 0),
+        ::addr::list::Entry::branch(b"amazonaws", 15, 1),
+        ::addr::list::Entry::private(b"github", 16, 0),
+        ::addr::list::Entry::icann(b"co", 16, 1),
+        ::addr::list::Entry::branch(b"compute", 17, 1),
+        ::addr::list::Entry::private(b"blogspot", 18, 0),
+        ::addr::list::Entry::private(b"*", 18, 0),
+    ],
+    ::addr::list::Metadata::from_parts(Some("2024-06-25_10-05-23_UTC"), Some("7e6b5e5e3e0b1c0a2d6a0e6c8b8f6e4f2c1a0b9d")),
+);
@@ -25,6 +25,10 @@ struct Test {
     valid: bool,
 }
 
+mod compiled {
+    include!("compiled/list.rs");
+}
+
 #[test]
 fn addr_parsing() {
     rspec::run(&rspec::given("a domain", (), |ctx| {
@@ -109,7 +113,7 @@ fn addr_parsing() {
         ctx.it("should not have a label > 63 characters", |_| {
             let mut too_long_domain = String::from("a");
             for _ in 0..64 {
-                too_long_domain.push_str("a");
+                too_long_domain.push('a');
             }
             too_long_domain.push_str(".com");
             assert!(addr::parse_domain_name(too_long_domain.as_str()).is_err());
@@ -132,6 +136,10 @@ fn addr_parsing() {
 
         ctx.it("should not allow number only tlds", |_| {
             assert!(addr::parse_domain_name("example.127").is_err());
+            assert!(addr::parse_domain_name("example.1e5").is_err());
+            assert!(addr::parse_domain_name("example.nan").is_err());
+            assert!(addr::parse_domain_name("example.inf").is_err());
+            assert!(addr::parse_domain_name("example.0x7f").is_ok());
         });
 
         ctx.it("should not have more than 127 labels", |_| {
@@ -152,6 +160,125 @@ fn addr_parsing() {
             assert!(addr::parse_domain_name(too_many_chars_domain.as_str()).is_err());
         });
 
+        ctx.it("should report where the error is", |_| {
+            let error = addr::parse_domain_name("www.exa mple.com").unwrap_err();
+            assert_eq!(error.span(), Some(7..8));
+            assert_eq!(error.label(), Some(1));
+            assert_eq!(
+                error.to_string(),
+                "'www.exa mple.com' contains an illegal character (label 1, bytes 7..8)"
+            );
+            assert!(format!("{:#}", error).ends_with("\nwww.exa mple.com\n       ^"));
+
+            let error = addr::parse_email_address("john@exa_mple.com").unwrap_err();
+            assert_eq!(error.span(), Some(8..9));
+            assert_eq!(error.label(), Some(0));
+        });
+
+        ctx.it("should report all the errors at once when asked", |_| {
+            use addr::error::Kind;
+            use addr::parser::DomainName;
+
+            let errors = psl::List
+                .diagnose_domain_name("-www.exa mple.com")
+                .unwrap_err();
+            let found: Vec<_> = errors.iter().map(|e| (e.kind(), e.span())).collect();
+            assert_eq!(
+                found,
+                vec![
+                    (Kind::LabelStartNotAlnum, Some(0..1)),
+                    (Kind::IllegalCharacter, Some(8..9)),
+                ]
+            );
+            assert!(psl::List.diagnose_domain_name("www.example.com").is_ok());
+        });
+
+        ctx.it("should allow errors to outlive the input", |_| {
+            let error: Box<dyn std::error::Error + Send + Sync + 'static> = {
+                let input = String::from("exa mple.com");
+                let error = addr::parse_domain_name(&input).unwrap_err();
+                Box::new(error.into_owned())
+            };
+            assert_eq!(
+                error.to_string(),
+                "'exa mple.com' contains an illegal character (label 0, bytes 3..4)"
+            );
+        });
+
+        ctx.it("should follow the rules of a custom policy", |_| {
+            use addr::error::Kind;
+            use addr::parser::DomainName;
+            use addr::policy::Policy;
+
+            let list = psl::List;
+            let strict = Policy::new()
+                .ascii_only(true)
+                .require_known_suffix(true)
+                .reject_private(true);
+            assert!(list.parse_domain_name_with("example.com", &strict).is_ok());
+            let error = list
+                .parse_domain_name_with("københavn.eu", &strict)
+                .unwrap_err();
+            assert_eq!(error.kind(), Kind::IllegalCharacter);
+            let error = list
+                .parse_domain_name_with("example.localhost", &strict)
+                .unwrap_err();
+            assert_eq!(error.kind(), Kind::UnknownSuffix);
+            let error = list
+                .parse_domain_name_with("example.github.io", &strict)
+                .unwrap_err();
+            assert_eq!(error.kind(), Kind::PrivateSuffix);
+
+            let relaxed = Policy::new().allow_underscore(true).allow_numeric_tld(true);
+            assert!(list
+                .parse_domain_name_with("_dmarc.example.com", &relaxed)
+                .is_ok());
+            assert!(list.parse_domain_name_with("example.127", &relaxed).is_ok());
+            assert!(list.parse_domain_name("_dmarc.example.com").is_err());
+
+            let octets = Policy::new().count_octets(true);
+            let name = format!("{}.dk", "ø".repeat(40));
+            assert!(list.parse_domain_name(&name).is_ok());
+            let error = list.parse_domain_name_with(&name, &octets).unwrap_err();
+            assert_eq!(error.kind(), Kind::LabelTooLong);
+
+            let shallow = Policy::new().max_labels(3);
+            assert!(list.parse_domain_name_with("a.b.c.d", &shallow).is_err());
+        });
+
+        ctx.it("should only reject reserved labels when asked", |_| {
+            use addr::error::Kind;
+            use addr::parser::DomainName;
+            use addr::policy::Policy;
+
+            let name = "r3---sn-4g5e6nz7.googlevideo.com";
+            let domain = addr::parse_domain_name(name).unwrap();
+            assert_eq!(domain.root(), Some("googlevideo.com"));
+            let policy = Policy::new().reject_reserved_labels(true);
+            let error = psl::List.parse_domain_name_with(name, &policy).unwrap_err();
+            assert_eq!(error.kind(), Kind::ReservedLabel);
+
+            let error = addr::parse_domain_name("xn--zzzz.com").unwrap_err();
+            assert_eq!(error.kind(), Kind::InvalidPunycode);
+        });
+
+        ctx.it("should measure IDNs by their length on the wire", |_| {
+            use addr::error::Kind;
+            use addr::parser::DomainName;
+            use addr::policy::Policy;
+
+            // 27 code points but 77 octets once converted to an A-label
+            let name = "一二三四五六七八九十百千万亿兆京垓秭穰沟涧正载极恒河沙.中国";
+            let error = addr::parse_domain_name(name).unwrap_err();
+            assert_eq!(error.kind(), Kind::LabelTooLong);
+            let policy = Policy::new().wire_lengths(false);
+            let domain = psl::List.parse_domain_name_with(name, &policy).unwrap();
+            assert_eq!(domain.wire_len(), 1 + 77 + 1 + "xn--fiqs8s".len() + 1);
+
+            let domain = addr::parse_domain_name("www.example.com.").unwrap();
+            assert_eq!(domain.wire_len(), 17);
+        });
+
         ctx.it("should handle lifetimes correctly", |_| {
             let input = "sub.example.com";
             let root_domain = addr::parse_domain_name(input).unwrap().root().unwrap();
@@ -320,6 +447,200 @@ fn addr_parsing() {
             }
         });
     }));
+
+    rspec::run(&rspec::given("the rules of a list", (), |ctx| {
+        ctx.it("should find the same suffixes as publicsuffix", |_| {
+            use addr::parser::DomainName;
+
+            let dat = include_str!("public_suffix_list.dat");
+            let rules = addr::list::parse_rules(dat).unwrap();
+            let list: publicsuffix::List = dat.parse().unwrap();
+            let names = vec![
+                "example.com",
+                "www.example.co.uk",
+                "a.b.example.uk.com",
+                "www.ck",
+                "test.www.ck",
+                "foo.bar.ck",
+                "example.github.io",
+                "www.食狮.中国",
+                "example.unknown",
+            ];
+            for name in names {
+                let expected = list.parse_domain_name(name).unwrap();
+                let found = rules.parse_domain_name(name).unwrap();
+                assert_eq!(found.suffix(), expected.suffix(), "{}", name);
+                assert_eq!(found.root(), expected.root(), "{}", name);
+                assert_eq!(found.is_private(), expected.is_private(), "{}", name);
+            }
+        });
+
+        ctx.it("should enumerate and count the rules", |_| {
+            let dat = include_str!("public_suffix_list.dat");
+            let rules = addr::list::parse_rules(dat).unwrap();
+            let lines = dat
+                .lines()
+                .filter(|line| !line.is_empty() && !line.starts_with("//"))
+                .count();
+            assert_eq!(rules.len(), lines);
+            assert!(rules.under("uk").any(|rule| rule.as_str() == "co.uk"));
+            assert!(rules.iter().any(|rule| rule.is_wildcard()));
+            assert!(addr::list::is_suffix(&rules, "co.uk"));
+            assert!(addr::list::is_suffix(&addr::psl::List, "co.uk"));
+        });
+
+        ctx.it("should parse with only one section of the list", |_| {
+            let domain = addr::parse_domain_name_icann("www.example.github.io").unwrap();
+            assert_eq!(domain.root(), Some("github.io"));
+            assert_eq!(domain.suffix(), "io");
+            assert!(domain.is_icann());
+
+            let domain = addr::parse_domain_name_private("www.example.github.io").unwrap();
+            assert_eq!(domain.root(), Some("example.github.io"));
+
+            let domain = addr::parse_domain_name_icann("www.example.co.uk").unwrap();
+            assert_eq!(domain.root(), Some("example.co.uk"));
+
+            let domain = addr::parse_domain_name_private("www.example.co.uk").unwrap();
+            assert_eq!(domain.root(), Some("co.uk"));
+            assert!(!domain.has_known_suffix());
+
+            let name = addr::parse_dns_name_icann("_dmarc.example.github.io").unwrap();
+            assert_eq!(name.root(), Some("github.io"));
+        });
+
+        ctx.it("should compile a list into code", |_| {
+            use addr::parser::DomainName;
+
+            let dat = include_str!("compiled/public_suffix_list.dat");
+            let source = addr::build::compile(dat).unwrap();
+            assert_eq!(
+                source,
+                include_str!("compiled/list.rs"),
+                "regenerate the list"
+            );
+
+            let rules = addr::list::parse_rules(dat).unwrap();
+            let names = vec![
+                "www.example.com",
+                "www.example.co.uk",
+                "blog.blogspot.co.uk",
+                "test.www.ck",
+                "foo.bar.ck",
+                "example.github.io",
+                "a.b.eu-west-1.compute.amazonaws.com",
+                "www.食狮.公司.cn",
+                "www.example.xn--fiqs8s",
+                "www.xn--85x722f.xn--55qx5d.cn",
+                "example.unknown",
+            ];
+            for name in names {
+                let expected = rules.parse_domain_name(name).unwrap();
+                let found = compiled::LIST.parse_domain_name(name).unwrap();
+                assert_eq!(found.suffix(), expected.suffix(), "{}", name);
+                assert_eq!(found.root(), expected.root(), "{}", name);
+                assert_eq!(found.is_private(), expected.is_private(), "{}", name);
+            }
+            assert_eq!(compiled::LIST.metadata(), rules.metadata());
+        });
+
+        ctx.it("should report the domains affected by a new list", |_| {
+            use addr::parser::DomainName;
+
+            let dat = include_str!("public_suffix_list.dat");
+            let old = addr::list::parse_rules(dat).unwrap();
+            let edited = dat.replace("\ngithub.io\n", "\n");
+            let new = addr::list::parse_rules(&edited).unwrap();
+
+            let changes = addr::list::Changes::between(&old, &new);
+            assert_eq!(changes.removed().len(), 1);
+            assert_eq!(changes.removed()[0].as_str(), "github.io");
+
+            let names = vec!["www.example.com", "user.github.io", "www.example.co.uk"];
+            let names = names
+                .into_iter()
+                .map(|name| addr::psl::List.parse_domain_name(name).unwrap());
+            let changed = addr::list::affected(&new, names);
+            assert_eq!(changed.len(), 1);
+            assert_eq!(changed[0].before().root(), Some("user.github.io"));
+            assert_eq!(changed[0].after().unwrap().root(), Some("github.io"));
+        });
+    }));
+
+    rspec::run(&rspec::given("a domain map", (), |ctx| {
+        ctx.it("should find the most specific entry for a name", |_| {
+            use addr::map::{DomainMap, Scope};
+
+            let mut policy = DomainMap::new();
+            policy.insert_subtree("example.com", "allow");
+            policy.insert_subtree("ads.example.com", "block");
+            policy.insert("ads.example.com", "warn");
+
+            let lookup = |name| {
+                let name = addr::parse_domain_name(name).unwrap();
+                policy.longest_match(&name).map(|found| *found.value())
+            };
+            assert_eq!(lookup("www.example.com"), Some("allow"));
+            assert_eq!(lookup("pixel.ads.example.com"), Some("block"));
+            assert_eq!(lookup("ads.example.com"), Some("warn"));
+            assert_eq!(lookup("example.net"), None);
+
+            let names = policy
+                .iter()
+                .map(|entry| (entry.name().to_string(), entry.scope()))
+                .collect::<Vec<_>>();
+            assert_eq!(
+                names,
+                vec![
+                    ("example.com".to_string(), Scope::Subtree),
+                    ("ads.example.com".to_string(), Scope::Exact),
+                    ("ads.example.com".to_string(), Scope::Subtree),
+                ]
+            );
+            let groups = policy.group_by_root(&addr::psl::List);
+            assert_eq!(groups.len(), 1);
+            assert_eq!(groups["example.com"].len(), 3);
+        });
+    }));
+
+    rspec::run(&rspec::given("a blocklist", (), |ctx| {
+        ctx.it(
+            "should combine rules from lists in different formats",
+            |_| {
+                use addr::blocklist::{self, Action, Format, Matcher};
+
+                let hosts = "0.0.0.0 ads.example.com\n0.0.0.0 bad..example.com";
+                let adblock = "||tracker.example.net^\n@@||ok.tracker.example.net^";
+                let domains = "# plain\n*.malware.example.org";
+
+                let mut matcher = Matcher::default();
+                let mut errors = Vec::new();
+                for &(input, format) in &[
+                    (hosts, Format::Hosts),
+                    (adblock, Format::Adblock),
+                    (domains, Format::Domains),
+                ] {
+                    let (rules, found) = blocklist::parse(&addr::psl::List, input, format);
+                    matcher.extend(rules);
+                    errors.extend(found);
+                }
+
+                assert_eq!(matcher.rules().len(), 4);
+                assert_eq!(errors.len(), 1);
+                assert_eq!(errors[0].line(), 2);
+                assert_eq!(errors[0].error().input(), "0.0.0.0 bad..example.com");
+                assert_eq!(errors[0].error().span(), Some(12..12));
+
+                assert!(matcher.is_blocked("ads.example.com"));
+                assert!(matcher.is_blocked("a.b.tracker.example.net"));
+                assert!(matcher.is_blocked("x.malware.example.org"));
+                assert!(matcher.is_blocked("malware.example.org."));
+                let rule = matcher.check("ok.tracker.example.net").unwrap();
+                assert_eq!(rule.action(), Action::Allow);
+                assert!(matcher.check("example.com").is_none());
+            },
+        );
+    }));
 }
 
 // Converts a String to &'static str